@@ -0,0 +1,183 @@
+use alloy::primitives::U256;
+use bigdecimal::{
+    num_bigint::{BigInt, Sign},
+    BigDecimal, RoundingMode,
+};
+
+use crate::error::InternalError;
+
+/// The number of digits of [`U256::MAX`].
+const MAX_DIGITS: i64 = 78;
+
+/// An amount of tokens, either expressed in the token smallest unit, or as a
+/// human readable decimal value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Amount {
+    /// An amount expressed in the token smallest unit.
+    Raw(U256),
+    /// An amount expressed as a decimal value, scaled using the token
+    /// decimals.
+    Decimal(BigDecimal),
+}
+
+impl Amount {
+    /// Returns the amount in the token smallest unit, given the token
+    /// decimals.
+    pub(crate) fn to_raw(&self, decimals: u8) -> Result<U256, InternalError> {
+        match self {
+            Amount::Raw(amount) => Ok(*amount),
+            Amount::Decimal(amount) => from_decimal(amount, decimals, Rounding::Exact),
+        }
+    }
+}
+
+impl From<U256> for Amount {
+    fn from(value: U256) -> Self {
+        Amount::Raw(value)
+    }
+}
+
+impl From<BigDecimal> for Amount {
+    fn from(value: BigDecimal) -> Self {
+        Amount::Decimal(value)
+    }
+}
+
+/// How to round a decimal amount having more decimals than its token.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// The amount is not rounded, and fails with an excess precision error.
+    #[default]
+    Exact,
+    /// The amount is rounded towards zero.
+    Down,
+    /// The amount is rounded away from zero.
+    Up,
+    /// The amount is rounded to the nearest value, away from zero when
+    /// halfway.
+    HalfUp,
+    /// The amount is rounded to the nearest value, to the even one when
+    /// halfway.
+    HalfEven,
+}
+
+/// Scales the given amount in the token smallest unit to a decimal amount,
+/// the inverse of [`from_decimal`].
+pub(crate) fn to_decimal(amount: U256, decimals: u8) -> BigDecimal {
+    BigDecimal::from((
+        BigInt::from_bytes_be(Sign::Plus, &amount.to_be_bytes::<{ U256::BYTES }>()),
+        decimals as i64,
+    ))
+}
+
+/// Scales the given decimal amount to the token smallest unit, rounding it
+/// as requested.
+pub(crate) fn from_decimal(
+    amount: &BigDecimal,
+    decimals: u8,
+    rounding: Rounding,
+) -> Result<U256, InternalError> {
+    if amount.sign() == Sign::Minus {
+        return Err(InternalError::NegativeAmount(amount.clone()));
+    }
+
+    if amount.sign() == Sign::NoSign {
+        return Ok(U256::ZERO);
+    }
+
+    // The number of integer digits of the scaled amount, checked before
+    // scaling so that huge exponents don't build huge integers
+    let (_, scale) = amount.as_bigint_and_scale();
+    let magnitude = (amount.digits() as i64)
+        .saturating_sub(scale)
+        .saturating_add(decimals.into());
+
+    if magnitude > MAX_DIGITS {
+        return Err(InternalError::Overflow(amount.clone()));
+    }
+
+    // The scaled amount is below 0.1
+    if magnitude < 0 {
+        return match rounding {
+            Rounding::Exact => Err(InternalError::ExcessPrecision(amount.clone(), decimals)),
+            Rounding::Up => Ok(U256::from(1)),
+            _ => Ok(U256::ZERO),
+        };
+    }
+
+    let scaled = amount * BigDecimal::new(BigInt::from(1), -(decimals as i64));
+
+    let mode = match rounding {
+        Rounding::Exact if !scaled.is_integer() => {
+            return Err(InternalError::ExcessPrecision(amount.clone(), decimals))
+        }
+        Rounding::Exact | Rounding::Down => RoundingMode::Down,
+        Rounding::Up => RoundingMode::Up,
+        Rounding::HalfUp => RoundingMode::HalfUp,
+        Rounding::HalfEven => RoundingMode::HalfEven,
+    };
+
+    let (digits, _) = scaled.with_scale_round(0, mode).into_bigint_and_exponent();
+    let (_, bytes) = digits.to_bytes_be();
+
+    U256::try_from_be_slice(&bytes).ok_or_else(|| InternalError::Overflow(amount.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use alloy::primitives::U256;
+    use bigdecimal::BigDecimal;
+
+    use super::{from_decimal, Rounding};
+
+    #[test]
+    fn test_from_decimal() {
+        let amount = BigDecimal::from_str("12.5").unwrap();
+
+        assert_eq!(
+            from_decimal(&amount, 6, Rounding::Exact).unwrap(),
+            U256::from(12_500_000_u64)
+        );
+        assert!(from_decimal(&amount, 0, Rounding::Exact).is_err());
+        assert_eq!(
+            from_decimal(&amount, 0, Rounding::HalfEven).unwrap(),
+            U256::from(12)
+        );
+        assert_eq!(
+            from_decimal(&amount, 0, Rounding::HalfUp).unwrap(),
+            U256::from(13)
+        );
+        assert!(from_decimal(&BigDecimal::from_str("-1").unwrap(), 6, Rounding::Down).is_err());
+    }
+
+    #[test]
+    fn test_from_decimal_huge_exponent() {
+        let huge = BigDecimal::from_str("1e10000000").unwrap();
+        let tiny = BigDecimal::from_str("1e-10000000").unwrap();
+
+        assert!(from_decimal(&huge, 18, Rounding::Exact).is_err());
+        assert!(from_decimal(&tiny, 18, Rounding::Exact).is_err());
+        assert_eq!(from_decimal(&tiny, 18, Rounding::Down).unwrap(), U256::ZERO);
+        assert_eq!(
+            from_decimal(&tiny, 18, Rounding::Up).unwrap(),
+            U256::from(1)
+        );
+        assert_eq!(
+            from_decimal(
+                &BigDecimal::from_str("0e10000000").unwrap(),
+                18,
+                Rounding::Exact
+            )
+            .unwrap(),
+            U256::ZERO
+        );
+
+        // The largest amounts are still accepted
+        let max = BigDecimal::from_str(&U256::MAX.to_string()).unwrap();
+
+        assert_eq!(from_decimal(&max, 0, Rounding::Exact).unwrap(), U256::MAX);
+        assert!(from_decimal(&max, 1, Rounding::Exact).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+//! Serializes addresses as [EIP-55] checksummed strings.
+//!
+//! [EIP-55]: https://eips.ethereum.org/EIPS/eip-55
+
+use alloy::primitives::Address;
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub(crate) fn serialize<S: Serializer>(
+    address: &Address,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&address.to_checksum(None))
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+    Address::deserialize(deserializer)
+}
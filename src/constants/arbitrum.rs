@@ -3,9 +3,20 @@ use once_cell::sync::Lazy;
 
 use crate::Token;
 
+/// Ether.
+pub static ETH: Lazy<Token> = Lazy::<Token>::new(|| {
+    Token::new(
+        42161,
+        address!("EeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE"),
+        String::from("ETH"),
+        18,
+    )
+});
+
 /// Wrapped Ether.
 pub static WETH: Lazy<Token> = Lazy::<Token>::new(|| {
     Token::new(
+        42161,
         address!("82aF49447D8a07e3bd95BD0d56f35241523fBab1"),
         String::from("WETH"),
         18,
@@ -15,6 +26,7 @@ pub static WETH: Lazy<Token> = Lazy::<Token>::new(|| {
 /// Circle USD.
 pub static USDC: Lazy<Token> = Lazy::<Token>::new(|| {
     Token::new(
+        42161,
         address!("af88d065e77c8cC2239327C5EDb3A432268e5831"),
         String::from("USDC"),
         6,
@@ -24,6 +36,7 @@ pub static USDC: Lazy<Token> = Lazy::<Token>::new(|| {
 /// Tether USD.
 pub static USDT: Lazy<Token> = Lazy::<Token>::new(|| {
     Token::new(
+        42161,
         address!("Fd086bC7CD5C481DCC9C85ebE478A1C0b69FCbb9"),
         String::from("USDT"),
         6,
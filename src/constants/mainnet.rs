@@ -6,6 +6,7 @@ use crate::Token;
 /// Ether.
 pub static ETH: Lazy<Token> = Lazy::<Token>::new(|| {
     Token::new(
+        1,
         address!("EeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE"),
         String::from("ETH"),
         18,
@@ -15,6 +16,7 @@ pub static ETH: Lazy<Token> = Lazy::<Token>::new(|| {
 /// Wrapped Ether.
 pub static WETH: Lazy<Token> = Lazy::<Token>::new(|| {
     Token::new(
+        1,
         address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
         String::from("WETH"),
         18,
@@ -24,6 +26,7 @@ pub static WETH: Lazy<Token> = Lazy::<Token>::new(|| {
 /// Wrapped Bitcoin.
 pub static WBTC: Lazy<Token> = Lazy::<Token>::new(|| {
     Token::new(
+        1,
         address!("2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599"),
         String::from("WBTC"),
         8,
@@ -33,6 +36,7 @@ pub static WBTC: Lazy<Token> = Lazy::<Token>::new(|| {
 /// Circle USD.
 pub static USDC: Lazy<Token> = Lazy::<Token>::new(|| {
     Token::new(
+        1,
         address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
         String::from("USDC"),
         6,
@@ -42,6 +46,7 @@ pub static USDC: Lazy<Token> = Lazy::<Token>::new(|| {
 /// Tether USD.
 pub static USDT: Lazy<Token> = Lazy::<Token>::new(|| {
     Token::new(
+        1,
         address!("dAC17F958D2ee523a2206206994597C13D831ec7"),
         String::from("USDT"),
         6,
@@ -51,6 +56,7 @@ pub static USDT: Lazy<Token> = Lazy::<Token>::new(|| {
 /// Dai stablecoin.
 pub static DAI: Lazy<Token> = Lazy::<Token>::new(|| {
     Token::new(
+        1,
         address!("6B175474E89094C44Da98b954EedeAC495271d0F"),
         String::from("DAI"),
         18,
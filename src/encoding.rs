@@ -0,0 +1,64 @@
+use alloy::{primitives::B256, sol_types::SolValue};
+
+/// The ABI type a token uses to return its `symbol` and `name`.
+///
+/// Most tokens return a `string`, but some legacy ones (like MKR or SAI)
+/// return a `bytes32` instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringEncoding {
+    /// The value is returned as a `string`.
+    String,
+    /// The value is returned as a null padded `bytes32`.
+    Bytes32,
+}
+
+impl StringEncoding {
+    /// Decodes the given return data as a `string`, falling back to a
+    /// `bytes32` if it fails.
+    pub(crate) fn decode(data: &[u8]) -> Result<(String, Self), alloy::sol_types::Error> {
+        match String::abi_decode(data, true) {
+            Ok(value) => Ok((value, Self::String)),
+            Err(_) if data.len() == B256::len_bytes() => {
+                let value = B256::abi_decode(data, true)?;
+                let len = value
+                    .iter()
+                    .rposition(|b| *b != 0)
+                    .map_or(0, |index| index + 1);
+
+                Ok((
+                    String::from_utf8_lossy(&value[..len]).into_owned(),
+                    Self::Bytes32,
+                ))
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::b256, sol_types::SolValue};
+
+    use super::StringEncoding;
+
+    #[test]
+    fn test_decode_string() {
+        let data = String::from("DAI").abi_encode();
+
+        assert_eq!(
+            StringEncoding::decode(&data).unwrap(),
+            (String::from("DAI"), StringEncoding::String)
+        );
+    }
+
+    #[test]
+    fn test_decode_bytes32() {
+        // MKR symbol
+        let data = b256!("4d4b520000000000000000000000000000000000000000000000000000000000");
+
+        assert_eq!(
+            StringEncoding::decode(data.as_slice()).unwrap(),
+            (String::from("MKR"), StringEncoding::Bytes32)
+        );
+    }
+}
@@ -32,10 +32,45 @@ impl Display for Error {
 pub enum InternalError {
     #[error("The token {0} is not present in the store")]
     NotInStore(String),
+    #[error("The native asset of chain {0} is unknown")]
+    UnknownNativeToken(u64),
+    #[error("The native asset has no contract to call {0} on")]
+    NativeToken(&'static str),
+    #[error("The symbol {0} is shared by several tokens: {1:?}")]
+    Ambiguous(String, Vec<alloy::primitives::Address>),
     #[error("Failed to query token: {0}")]
     Transport(#[from] alloy::transports::TransportError),
     #[error("Contract error: {0}")]
     Contract(#[from] alloy::contract::Error),
     #[error("Failed to decode token: {0}")]
     Sol(#[from] alloy::sol_types::Error),
+    #[error("The call to {0} failed")]
+    CallFailed(&'static str),
+    #[error("The aggregated calls failed: {0}")]
+    BatchFailed(std::sync::Arc<alloy::contract::Error>),
+    #[error("The call to {0} returned false")]
+    ReturnedFalse(&'static str),
+    #[error("The call to {0} targets an address without code")]
+    NoCode(&'static str),
+    #[error("The call to {0} reverted: {1}")]
+    Reverted(&'static str, String),
+    #[cfg(feature = "redis-store")]
+    #[error("Redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("Failed to sign: {0}")]
+    Signer(#[from] alloy::signers::Error),
+    #[error("Failed to determine the EIP-712 domain")]
+    UnknownDomain,
+    #[error("The amount {0} has more than {1} decimals")]
+    ExcessPrecision(bigdecimal::BigDecimal, u8),
+    #[error("The amount {0} is negative")]
+    NegativeAmount(bigdecimal::BigDecimal),
+    #[error("The amount {0} overflows a U256")]
+    Overflow(bigdecimal::BigDecimal),
+    #[error("The amount {0} is not a valid decimal number")]
+    InvalidAmount(String),
+    #[error("The ratio denominator is zero")]
+    ZeroDenominator,
+    #[error("The amount can't be mixed with an amount of token {0}")]
+    TokenMismatch(alloy::primitives::Address),
 }
@@ -0,0 +1,190 @@
+use alloy::{
+    primitives::{Address, TxHash, U256},
+    rpc::types::{Log, TransactionReceipt},
+    sol_types::SolEvent,
+};
+use bigdecimal::BigDecimal;
+
+use crate::{provider::Erc20Contract, Token, TokenId, TokenStore};
+
+/// An ERC-20 `Transfer` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transfer {
+    /// The token address.
+    pub token: Address,
+    /// The address the tokens are moved from.
+    pub from: Address,
+    /// The address the tokens are moved to.
+    pub to: Address,
+    /// The amount of tokens moved.
+    pub value: U256,
+}
+
+impl Transfer {
+    /// Decodes a `Transfer` event from the given log, if it is one.
+    pub fn decode_log(log: &Log) -> Option<Self> {
+        Erc20Contract::Transfer::decode_log(&log.inner, true)
+            .ok()
+            .map(|log| Self {
+                token: log.address,
+                from: log.data.from,
+                to: log.data.to,
+                value: log.data.value,
+            })
+    }
+
+    /// Returns all the `Transfer` events emitted in the given receipt.
+    pub fn from_receipt(receipt: &TransactionReceipt) -> Vec<Self> {
+        receipt
+            .inner
+            .logs()
+            .iter()
+            .filter_map(Self::decode_log)
+            .collect()
+    }
+}
+
+/// An ERC-20 `Approval` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Approval {
+    /// The token address.
+    pub token: Address,
+    /// The address owning the tokens.
+    pub owner: Address,
+    /// The address allowed to spend the tokens.
+    pub spender: Address,
+    /// The allowed amount of tokens.
+    pub value: U256,
+}
+
+impl Approval {
+    /// Decodes an `Approval` event from the given log, if it is one.
+    pub fn decode_log(log: &Log) -> Option<Self> {
+        Erc20Contract::Approval::decode_log(&log.inner, true)
+            .ok()
+            .map(|log| Self {
+                token: log.address,
+                owner: log.data.owner,
+                spender: log.data.spender,
+                value: log.data.value,
+            })
+    }
+
+    /// Returns all the `Approval` events emitted in the given receipt.
+    pub fn from_receipt(receipt: &TransactionReceipt) -> Vec<Self> {
+        receipt
+            .inner
+            .logs()
+            .iter()
+            .filter_map(Self::decode_log)
+            .collect()
+    }
+}
+
+/// A `Transfer` event, along with its [`Token`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferEvent {
+    /// The token.
+    pub token: Token,
+    /// The address the tokens are moved from.
+    pub from: Address,
+    /// The address the tokens are moved to.
+    pub to: Address,
+    /// The amount of tokens moved.
+    pub amount: U256,
+    /// The amount of tokens moved, scaled using the token decimals.
+    pub value: BigDecimal,
+    /// The block number the event was emitted in.
+    pub block_number: Option<u64>,
+    /// The hash of the transaction that emitted the event.
+    pub transaction_hash: Option<TxHash>,
+    /// The index of the event in its block.
+    pub log_index: Option<u64>,
+    /// Whether the event has been removed by a chain reorganization.
+    pub removed: bool,
+}
+
+impl TransferEvent {
+    /// Decodes a `Transfer` event emitted by the given token from the given
+    /// log, if it is one.
+    pub fn decode_log(log: &Log, token: &Token) -> Option<Self> {
+        let transfer = Transfer::decode_log(log).filter(|t| t.token == token.address)?;
+
+        Some(Self {
+            token: token.clone(),
+            from: transfer.from,
+            to: transfer.to,
+            amount: transfer.value,
+            value: token.get_balance(transfer.value),
+            block_number: log.block_number,
+            transaction_hash: log.transaction_hash,
+            log_index: log.log_index,
+            removed: log.removed,
+        })
+    }
+
+    /// Decodes a `Transfer` event from the given log, if it is one, looking
+    /// for its token in the given store.
+    pub fn from_store<'a, S>(log: &Log, chain_id: u64, store: &'a S) -> Option<Self>
+    where
+        S: TokenStore<'a>,
+    {
+        let token = store.get(chain_id, TokenId::Address(log.address()))?;
+
+        Self::decode_log(log, &token)
+    }
+}
+
+/// An `Approval` event, along with its [`Token`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApprovalEvent {
+    /// The token.
+    pub token: Token,
+    /// The address owning the tokens.
+    pub owner: Address,
+    /// The address allowed to spend the tokens.
+    pub spender: Address,
+    /// The allowed amount of tokens.
+    pub amount: U256,
+    /// The allowed amount of tokens, scaled using the token decimals.
+    pub value: BigDecimal,
+    /// The block number the event was emitted in.
+    pub block_number: Option<u64>,
+    /// The hash of the transaction that emitted the event.
+    pub transaction_hash: Option<TxHash>,
+    /// The index of the event in its block.
+    pub log_index: Option<u64>,
+    /// Whether the event has been removed by a chain reorganization.
+    pub removed: bool,
+}
+
+impl ApprovalEvent {
+    /// Decodes an `Approval` event emitted by the given token from the given
+    /// log, if it is one.
+    pub fn decode_log(log: &Log, token: &Token) -> Option<Self> {
+        let approval = Approval::decode_log(log).filter(|a| a.token == token.address)?;
+
+        Some(Self {
+            token: token.clone(),
+            owner: approval.owner,
+            spender: approval.spender,
+            amount: approval.value,
+            value: token.get_balance(approval.value),
+            block_number: log.block_number,
+            transaction_hash: log.transaction_hash,
+            log_index: log.log_index,
+            removed: log.removed,
+        })
+    }
+
+    /// Decodes an `Approval` event from the given log, if it is one, looking
+    /// for its token in the given store.
+    pub fn from_store<'a, S>(log: &Log, chain_id: u64, store: &'a S) -> Option<Self>
+    where
+        S: TokenStore<'a>,
+    {
+        let token = store.get(chain_id, TokenId::Address(log.address()))?;
+
+        Self::decode_log(log, &token)
+    }
+}
@@ -1,31 +1,92 @@
-use crate::provider::Erc20Contract;
+use crate::{
+    amount,
+    error::InternalError,
+    native::{is_native_sentinel, is_native_token, native_token},
+    provider::Erc20Contract,
+    safe::check_call,
+    Amount, Error, StringEncoding, Token,
+};
 use alloy::{
     contract::private::{Provider, Transport},
-    contract::Error,
+    eips::BlockId,
     network::Network,
-    primitives::{Address, U256},
+    primitives::{Address, B256, U256},
+    providers::PendingTransactionBuilder,
+    sol_types::SolCall,
 };
 use async_once_cell::OnceCell;
-use bigdecimal::{
-    num_bigint::{BigInt, Sign},
-    BigDecimal,
-};
-use futures::TryFutureExt;
+use bigdecimal::BigDecimal;
 use std::{
+    collections::{BTreeMap, VecDeque},
     fmt::Debug,
-    future::{ready, IntoFuture},
+    future::Future,
+    sync::Mutex,
 };
 
 #[derive(Debug)]
 /// A token with an embedded contract instance that lazily query the
 /// blockchain.
+///
+/// The native asset of the chain is also supported, at its sentinel address
+/// (see [`is_native_token`]) for the chains known by [`native_token`], or
+/// given with [`LazyToken::with_native`]: its name,
+/// symbol and decimals are known, its balances are retrieved using
+/// `eth_getBalance`, and it has no total supply nor allowances, and can't be
+/// transferred nor approved through this type.
 pub struct LazyToken<P, T, N> {
-    name: OnceCell<String>,
-    symbol: OnceCell<String>,
+    native: OnceCell<Option<Token>>,
+    name: OnceCell<(String, StringEncoding)>,
+    symbol: OnceCell<(String, StringEncoding)>,
     decimals: OnceCell<u8>,
+    pinned: Mutex<PinnedCache>,
     instance: Erc20Contract::Erc20ContractInstance<T, P, N>,
 }
 
+/// A query made at a block pinned by its hash, whose result can be cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum PinnedQuery {
+    TotalSupply,
+    BalanceOf(Address),
+    Allowance(Address, Address),
+}
+
+/// The results of the queries made at pinned blocks, the oldest ones being
+/// evicted past [`PinnedCache::CAPACITY`].
+#[derive(Debug)]
+struct PinnedCache {
+    values: BTreeMap<(B256, PinnedQuery), U256>,
+    order: VecDeque<(B256, PinnedQuery)>,
+}
+
+impl PinnedCache {
+    const CAPACITY: usize = 1024;
+
+    const fn new() -> Self {
+        Self {
+            values: BTreeMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&self, key: &(B256, PinnedQuery)) -> Option<U256> {
+        self.values.get(key).copied()
+    }
+
+    fn insert(&mut self, key: (B256, PinnedQuery), value: U256) {
+        if self.values.insert(key, value).is_some() {
+            return;
+        }
+
+        self.order.push_back(key);
+
+        if self.order.len() > Self::CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.values.remove(&oldest);
+            }
+        }
+    }
+}
+
 impl<P, T, N> LazyToken<P, T, N>
 where
     P: Provider<T, N>,
@@ -35,13 +96,22 @@ where
     /// Creates a new [`LazyToken`].
     pub const fn new(address: Address, provider: P) -> Self {
         Self {
+            native: OnceCell::new(),
             name: OnceCell::new(),
             symbol: OnceCell::new(),
             decimals: OnceCell::new(),
+            pinned: Mutex::new(PinnedCache::new()),
             instance: Erc20Contract::new(address, provider),
         }
     }
 
+    /// Uses the given token as the native asset of the chain, whatever its
+    /// address, like the gas token of a chain unknown to [`native_token`].
+    pub fn with_native(mut self, token: Token) -> Self {
+        self.native = OnceCell::new_with(Some(token));
+        self
+    }
+
     /// Returns the token contract address.
     pub const fn address(&self) -> &Address {
         self.instance.address()
@@ -49,83 +119,471 @@ where
 
     /// Returns the name of the token.
     pub async fn name(&self) -> Result<&String, Error> {
-        self.name
-            .get_or_try_init(
-                self.instance
-                    .name()
-                    .call()
-                    .into_future()
-                    .and_then(|r| ready(Ok(r._0))),
-            )
+        self.name_with_encoding().await.map(|(name, _)| name)
+    }
+
+    /// Returns the [`StringEncoding`] used by the token to return its name.
+    pub async fn name_encoding(&self) -> Result<StringEncoding, Error> {
+        self.name_with_encoding()
             .await
+            .map(|(_, encoding)| *encoding)
     }
 
     /// Returns the symbol of the token.
     pub async fn symbol(&self) -> Result<&String, Error> {
+        self.symbol_with_encoding().await.map(|(symbol, _)| symbol)
+    }
+
+    /// Returns the [`StringEncoding`] used by the token to return its symbol.
+    pub async fn symbol_encoding(&self) -> Result<StringEncoding, Error> {
+        self.symbol_with_encoding()
+            .await
+            .map(|(_, encoding)| *encoding)
+    }
+
+    async fn name_with_encoding(&self) -> Result<&(String, StringEncoding), Error> {
+        self.name
+            .get_or_try_init(async {
+                if let Some(native) = self.native().await? {
+                    let name = native
+                        .metadata
+                        .as_ref()
+                        .and_then(|metadata| metadata.name.clone())
+                        .unwrap_or_else(|| native.symbol.clone());
+
+                    return Ok((name, StringEncoding::String));
+                }
+
+                let data = self
+                    .instance
+                    .name()
+                    .call_raw()
+                    .await
+                    .map_err(|err| self.error(err))?;
+
+                StringEncoding::decode(&data).map_err(|err| self.error(err))
+            })
+            .await
+    }
+
+    async fn symbol_with_encoding(&self) -> Result<&(String, StringEncoding), Error> {
         self.symbol
-            .get_or_try_init(
-                self.instance
+            .get_or_try_init(async {
+                if let Some(native) = self.native().await? {
+                    return Ok((native.symbol.clone(), StringEncoding::String));
+                }
+
+                let data = self
+                    .instance
                     .symbol()
-                    .call()
-                    .into_future()
-                    .and_then(|r| ready(Ok(r._0))),
-            )
+                    .call_raw()
+                    .await
+                    .map_err(|err| self.error(err))?;
+
+                StringEncoding::decode(&data).map_err(|err| self.error(err))
+            })
             .await
     }
 
     /// Returns the decimals places of the token.
     pub async fn decimals(&self) -> Result<&u8, Error> {
         self.decimals
-            .get_or_try_init(
+            .get_or_try_init(async {
+                if let Some(native) = self.native().await? {
+                    return Ok(native.decimals);
+                }
+
                 self.instance
                     .decimals()
                     .call()
-                    .into_future()
-                    .and_then(|r| ready(Ok(r._0))),
-            )
+                    .await
+                    .map(|result| result._0)
+                    .map_err(|err| self.error(err))
+            })
+            .await
+    }
+
+    /// Returns the native asset of the chain if this token is it, without
+    /// querying the chain id otherwise.
+    async fn native(&self) -> Result<Option<&Token>, Error> {
+        if self.native.get().is_none() && !is_native_sentinel(*self.address()) {
+            return Ok(None);
+        }
+
+        self.native
+            .get_or_try_init(async {
+                let chain_id = self
+                    .instance
+                    .provider()
+                    .get_chain_id()
+                    .await
+                    .map_err(|err| self.error(err))?;
+
+                // The sentinel of another chain may be a token on this one
+                if !is_native_token(chain_id, *self.address()) {
+                    return Ok(None);
+                }
+
+                native_token(chain_id)
+                    .map(Some)
+                    .ok_or_else(|| self.error(InternalError::UnknownNativeToken(chain_id)))
+            })
             .await
+            .map(Option::as_ref)
     }
 
     /// Returns the amount of tokens in existence.
     pub async fn total_supply(&self) -> Result<U256, Error> {
-        self.instance
-            .totalSupply()
-            .call()
-            .into_future()
-            .and_then(|r| ready(Ok(r._0)))
-            .await
+        self.total_supply_at(BlockId::latest()).await
+    }
+
+    /// Returns the amount of tokens in existence at the given block.
+    pub async fn total_supply_at(&self, block: BlockId) -> Result<U256, Error> {
+        self.check_not_native(Erc20Contract::totalSupplyCall::SIGNATURE)
+            .await?;
+
+        self.pinned(block, PinnedQuery::TotalSupply, async {
+            self.instance
+                .totalSupply()
+                .block(block)
+                .call()
+                .await
+                .map(|result| result._0)
+                .map_err(|err| self.error(err))
+        })
+        .await
     }
 
     /// Returns the value of tokens owned by `account`.
     pub async fn balance_of(&self, account: Address) -> Result<U256, Error> {
-        self.instance
-            .balanceOf(account)
-            .call()
-            .into_future()
-            .and_then(|r| ready(Ok(r.balance)))
-            .await
+        self.balance_of_at(account, BlockId::latest()).await
+    }
+
+    /// Returns the value of tokens owned by `account` at the given block.
+    pub async fn balance_of_at(&self, account: Address, block: BlockId) -> Result<U256, Error> {
+        self.pinned(block, PinnedQuery::BalanceOf(account), async {
+            if self.native().await?.is_some() {
+                return self
+                    .instance
+                    .provider()
+                    .get_balance(account)
+                    .block_id(block)
+                    .await
+                    .map_err(|err| self.error(err));
+            }
+
+            self.instance
+                .balanceOf(account)
+                .block(block)
+                .call()
+                .await
+                .map(|result| result.balance)
+                .map_err(|err| self.error(err))
+        })
+        .await
     }
 
     /// Returns the remaining number of tokens that `spender` will be
     /// allowed to spend on behalf of `owner`.
     pub async fn allowance(&self, owner: Address, spender: Address) -> Result<U256, Error> {
-        self.instance
-            .allowance(owner, spender)
-            .call()
-            .into_future()
-            .and_then(|r| ready(Ok(r._0)))
-            .await
+        self.allowance_at(owner, spender, BlockId::latest()).await
+    }
+
+    /// Returns the number of tokens that `spender` was allowed to spend on
+    /// behalf of `owner` at the given block.
+    pub async fn allowance_at(
+        &self,
+        owner: Address,
+        spender: Address,
+        block: BlockId,
+    ) -> Result<U256, Error> {
+        self.check_not_native(Erc20Contract::allowanceCall::SIGNATURE)
+            .await?;
+
+        self.pinned(block, PinnedQuery::Allowance(owner, spender), async {
+            self.instance
+                .allowance(owner, spender)
+                .block(block)
+                .call()
+                .await
+                .map(|result| result._0)
+                .map_err(|err| self.error(err))
+        })
+        .await
+    }
+
+    /// Runs the given query, caching its result if the block is pinned by
+    /// its hash, as such a result can't change. Only the results of the
+    /// latest [`PinnedCache::CAPACITY`] queries are kept.
+    async fn pinned<F>(&self, block: BlockId, query: PinnedQuery, f: F) -> Result<U256, Error>
+    where
+        F: Future<Output = Result<U256, Error>>,
+    {
+        let BlockId::Hash(hash) = block else {
+            return f.await;
+        };
+
+        let key = (hash.block_hash, query);
+
+        if let Some(value) = self.pinned.lock().unwrap().get(&key) {
+            return Ok(value);
+        }
+
+        let value = f.await?;
+
+        self.pinned.lock().unwrap().insert(key, value);
+
+        Ok(value)
     }
 
     /// Gets the token balance as a [`BigDecimal`]
     pub async fn get_balance(&self, amount: U256) -> Result<BigDecimal, Error> {
         let decimals = self.decimals().await?;
 
-        let balance = BigDecimal::from((
-            BigInt::from_bytes_be(Sign::Plus, &amount.to_be_bytes::<{ U256::BYTES }>()),
-            *decimals as i64,
-        ));
+        Ok(amount::to_decimal(amount, *decimals))
+    }
+
+    /// Builds a transaction moving `amount` tokens from the caller's account
+    /// to `to`.
+    pub async fn transfer_request(
+        &self,
+        to: Address,
+        amount: impl Into<Amount>,
+    ) -> Result<N::TransactionRequest, Error> {
+        self.check_not_native(Erc20Contract::transferCall::SIGNATURE)
+            .await?;
+
+        let amount = self.to_raw(amount.into()).await?;
+
+        Ok(self
+            .instance
+            .transfer(to, amount)
+            .into_transaction_request())
+    }
+
+    /// Moves `amount` tokens from the caller's account to `to`.
+    pub async fn transfer(
+        &self,
+        to: Address,
+        amount: impl Into<Amount>,
+    ) -> Result<PendingTransactionBuilder<N>, Error> {
+        self.check_not_native(Erc20Contract::transferCall::SIGNATURE)
+            .await?;
+
+        let amount = self.to_raw(amount.into()).await?;
+
+        self.instance
+            .transfer(to, amount)
+            .send()
+            .await
+            .map_err(|err| self.error(err))
+    }
+
+    /// Builds a transaction setting `amount` as the allowance of `spender`
+    /// over the caller's tokens.
+    pub async fn approve_request(
+        &self,
+        spender: Address,
+        amount: impl Into<Amount>,
+    ) -> Result<N::TransactionRequest, Error> {
+        self.check_not_native(Erc20Contract::approveCall::SIGNATURE)
+            .await?;
+
+        let amount = self.to_raw(amount.into()).await?;
+
+        Ok(self
+            .instance
+            .approve(spender, amount)
+            .into_transaction_request())
+    }
+
+    /// Sets `amount` as the allowance of `spender` over the caller's tokens.
+    pub async fn approve(
+        &self,
+        spender: Address,
+        amount: impl Into<Amount>,
+    ) -> Result<PendingTransactionBuilder<N>, Error> {
+        self.check_not_native(Erc20Contract::approveCall::SIGNATURE)
+            .await?;
+
+        let amount = self.to_raw(amount.into()).await?;
+
+        self.instance
+            .approve(spender, amount)
+            .send()
+            .await
+            .map_err(|err| self.error(err))
+    }
+
+    /// Builds a transaction moving `amount` tokens from `from` to `to` using
+    /// the allowance mechanism.
+    pub async fn transfer_from_request(
+        &self,
+        from: Address,
+        to: Address,
+        amount: impl Into<Amount>,
+    ) -> Result<N::TransactionRequest, Error> {
+        self.check_not_native(Erc20Contract::transferFromCall::SIGNATURE)
+            .await?;
+
+        let amount = self.to_raw(amount.into()).await?;
+
+        Ok(self
+            .instance
+            .transferFrom(from, to, amount)
+            .into_transaction_request())
+    }
+
+    /// Moves `amount` tokens from `from` to `to` using the allowance
+    /// mechanism.
+    pub async fn transfer_from(
+        &self,
+        from: Address,
+        to: Address,
+        amount: impl Into<Amount>,
+    ) -> Result<PendingTransactionBuilder<N>, Error> {
+        self.check_not_native(Erc20Contract::transferFromCall::SIGNATURE)
+            .await?;
+
+        let amount = self.to_raw(amount.into()).await?;
+
+        self.instance
+            .transferFrom(from, to, amount)
+            .send()
+            .await
+            .map_err(|err| self.error(err))
+    }
+
+    /// Moves `amount` tokens from the caller's account to `to`, after
+    /// having simulated the call to make sure it succeeds.
+    ///
+    /// Tokens not returning any boolean (like USDT) are supported, while an
+    /// address without code fails.
+    pub async fn safe_transfer(
+        &self,
+        to: Address,
+        amount: impl Into<Amount>,
+    ) -> Result<PendingTransactionBuilder<N>, Error> {
+        self.check_not_native(Erc20Contract::transferCall::SIGNATURE)
+            .await?;
+
+        let amount = self.to_raw(amount.into()).await?;
+        let call = self.instance.transfer(to, amount);
+
+        check_call::<Erc20Contract::transferCall, _, _>(
+            self.instance.provider(),
+            *self.address(),
+            call.call_raw().await,
+        )
+        .await
+        .map_err(|err| self.error(err))?;
+
+        call.send().await.map_err(|err| self.error(err))
+    }
+
+    /// Sets `amount` as the allowance of `spender` over the caller's tokens,
+    /// after having simulated the call to make sure it succeeds.
+    ///
+    /// Tokens not returning any boolean (like USDT) are supported, while an
+    /// address without code fails.
+    pub async fn safe_approve(
+        &self,
+        spender: Address,
+        amount: impl Into<Amount>,
+    ) -> Result<PendingTransactionBuilder<N>, Error> {
+        self.check_not_native(Erc20Contract::approveCall::SIGNATURE)
+            .await?;
+
+        let amount = self.to_raw(amount.into()).await?;
+        let call = self.instance.approve(spender, amount);
+
+        check_call::<Erc20Contract::approveCall, _, _>(
+            self.instance.provider(),
+            *self.address(),
+            call.call_raw().await,
+        )
+        .await
+        .map_err(|err| self.error(err))?;
+
+        call.send().await.map_err(|err| self.error(err))
+    }
+
+    /// Moves `amount` tokens from `from` to `to` using the allowance
+    /// mechanism, after having simulated the call to make sure it succeeds.
+    ///
+    /// Tokens not returning any boolean (like USDT) are supported, while an
+    /// address without code fails.
+    pub async fn safe_transfer_from(
+        &self,
+        from: Address,
+        to: Address,
+        amount: impl Into<Amount>,
+    ) -> Result<PendingTransactionBuilder<N>, Error> {
+        self.check_not_native(Erc20Contract::transferFromCall::SIGNATURE)
+            .await?;
+
+        let amount = self.to_raw(amount.into()).await?;
+        let call = self.instance.transferFrom(from, to, amount);
+
+        check_call::<Erc20Contract::transferFromCall, _, _>(
+            self.instance.provider(),
+            *self.address(),
+            call.call_raw().await,
+        )
+        .await
+        .map_err(|err| self.error(err))?;
+
+        call.send().await.map_err(|err| self.error(err))
+    }
+
+    async fn to_raw(&self, amount: Amount) -> Result<U256, Error> {
+        match amount {
+            Amount::Raw(amount) => Ok(amount),
+            Amount::Decimal(_) => {
+                let decimals = self.decimals().await?;
+
+                amount.to_raw(*decimals).map_err(|err| self.error(err))
+            }
+        }
+    }
+
+    /// Fails if the token is the native asset of the chain, which has no
+    /// contract to call the given function of.
+    async fn check_not_native(&self, function: &'static str) -> Result<(), Error> {
+        match self.native().await? {
+            Some(_) => Err(self.error(InternalError::NativeToken(function))),
+            None => Ok(()),
+        }
+    }
+
+    /// Creates an [`Error`] related to the token.
+    fn error(&self, err: impl Into<InternalError>) -> Error {
+        Error::new((*self.address()).into(), err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{B256, U256};
+
+    use super::{PinnedCache, PinnedQuery};
+
+    #[test]
+    fn test_pinned_cache_capacity() {
+        let mut cache = PinnedCache::new();
+
+        for i in 0..=PinnedCache::CAPACITY {
+            cache.insert(
+                (B256::from(U256::from(i)), PinnedQuery::TotalSupply),
+                U256::from(i),
+            );
+        }
 
-        Ok(balance)
+        assert_eq!(cache.values.len(), PinnedCache::CAPACITY);
+        assert!(cache.get(&(B256::ZERO, PinnedQuery::TotalSupply)).is_none());
+        assert_eq!(
+            cache.get(&(B256::with_last_byte(1), PinnedQuery::TotalSupply)),
+            Some(U256::from(1))
+        );
     }
 }
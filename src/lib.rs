@@ -7,29 +7,79 @@
     clippy::missing_const_for_fn,
     rustdoc::all
 )]
+// The errors keep the underlying alloy errors as is, rather than boxed
+#![allow(clippy::result_large_err)]
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+#[cfg(feature = "serde")]
+mod checksum;
+
 mod constants;
 pub use constants::*;
 
+mod multicall;
+
+mod native;
+pub use native::{is_native_token, native_token, register_native_token, NATIVE_TOKEN_ADDRESS};
+
+mod permit;
+pub use permit::{DaiPermit, Permit};
+
+mod permit2;
+pub use permit2::{
+    permit2_domain, Permit2Allowance, PermitBatch, PermitDetails, PermitSingle, PermitTransferFrom,
+    TokenPermissions, PERMIT2_ADDRESS,
+};
+
 mod provider;
 pub use provider::Erc20ProviderExt;
 
+mod amount;
+pub use amount::{Amount, Rounding};
+
+mod encoding;
+pub use encoding::StringEncoding;
+
 mod error;
 pub use error::Error;
 
+mod events;
+pub use events::{Approval, ApprovalEvent, Transfer, TransferEvent};
+
 mod token;
-pub use token::Token;
+pub use token::{Token, TokenCapabilities, TokenMetadata};
+
+#[cfg(feature = "token-list")]
+mod token_list;
+#[cfg(feature = "token-list")]
+pub use token_list::{TagDefinition, TokenInfo, TokenList, TokenListError, TokenListVersion};
 
 mod lazy_token;
 pub use lazy_token::LazyToken;
 
+mod token_amount;
+pub use token_amount::{TokenAmount, TokenDelta};
+
 mod token_id;
 pub use token_id::TokenId;
 
+mod safe;
+
 mod stores;
-pub use stores::{BasicTokenStore, Entry, StoreIter, TokenStore};
+pub use stores::{
+    BasicTokenStore, Clock, Entry, LayerItem, LayeredTokenStore, StoreIter, StoreSnapshot,
+    SystemClock, TokenMut, TokenStore, TokenStoreMut, TtlTokenStore, WritePolicy,
+};
+
+#[cfg(feature = "concurrent-store")]
+pub use stores::ConcurrentTokenStore;
 
 #[cfg(feature = "lru-store")]
 pub use stores::LruTokenStore;
+
+#[cfg(feature = "redb-store")]
+pub use stores::RedbTokenStore;
+
+#[cfg(feature = "redis-store")]
+pub use stores::RedisTokenStore;
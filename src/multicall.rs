@@ -0,0 +1,44 @@
+use alloy::{
+    primitives::{address, Address},
+    sol,
+    sol_types::SolCall,
+};
+
+use crate::{error::InternalError, StringEncoding};
+
+/// The address Multicall3 is deployed at on most chains.
+pub(crate) const MULTICALL3_ADDRESS: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+
+/// The maximum number of calls aggregated in a single Multicall3 call, to
+/// stay within the gas and response size limits of the nodes.
+pub(crate) const MULTICALL3_BATCH_SIZE: usize = 500;
+
+sol!(
+    #[sol(rpc)]
+    Multicall3Contract,
+    "abi/multicall3.json"
+);
+
+/// Decodes the return data of a single call aggregated through
+/// [`Multicall3Contract`] with `allowFailure` set.
+pub(crate) fn decode_result<C: SolCall>(
+    result: &Multicall3::Result,
+) -> Result<C::Return, InternalError> {
+    if !result.success {
+        return Err(InternalError::CallFailed(C::SIGNATURE));
+    }
+
+    Ok(C::abi_decode_returns(&result.returnData, true)?)
+}
+
+/// Decodes the `string` or `bytes32` return data of a `symbol` or `name` call
+/// aggregated through [`Multicall3Contract`] with `allowFailure` set.
+pub(crate) fn decode_string_result<C: SolCall>(
+    result: &Multicall3::Result,
+) -> Result<(String, StringEncoding), InternalError> {
+    if !result.success {
+        return Err(InternalError::CallFailed(C::SIGNATURE));
+    }
+
+    Ok(StringEncoding::decode(&result.returnData)?)
+}
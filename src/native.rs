@@ -0,0 +1,72 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, RwLock},
+};
+
+use alloy::primitives::{address, Address};
+
+use crate::{arbitrum, mainnet, Token};
+
+/// The sentinel address commonly used to represent the native asset of a
+/// chain, like Ether on mainnet.
+pub const NATIVE_TOKEN_ADDRESS: Address = address!("EeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE");
+
+/// The native assets registered for each chain, Ether on mainnet and
+/// Arbitrum being known.
+static NATIVE_TOKENS: LazyLock<RwLock<HashMap<u64, Token>>> = LazyLock::new(|| {
+    RwLock::new(HashMap::from([
+        (1, mainnet::ETH.to_owned()),
+        (42161, arbitrum::ETH.to_owned()),
+    ]))
+});
+
+/// Registers the native asset of the chain of the given token, replacing the
+/// one registered before, if any.
+///
+/// The token address is the sentinel representing the native asset on its
+/// chain, usually [`NATIVE_TOKEN_ADDRESS`], but some chains use another one,
+/// like the address of the POL token contract on Polygon. The balances of the
+/// sentinel are then retrieved using `eth_getBalance` by the provider and
+/// [`LazyToken`](crate::LazyToken).
+///
+/// The registration is global to the process, see
+/// [`LazyToken::with_native`](crate::LazyToken::with_native) to use a native
+/// asset for a single token.
+pub fn register_native_token(token: Token) {
+    NATIVE_TOKENS.write().unwrap().insert(token.chain_id, token);
+}
+
+/// Returns the native asset of the given chain, if known.
+///
+/// Only Ether on mainnet and Arbitrum is known, unless another native asset
+/// has been registered with [`register_native_token`].
+pub fn native_token(chain_id: u64) -> Option<Token> {
+    NATIVE_TOKENS.read().unwrap().get(&chain_id).cloned()
+}
+
+/// Returns whether the given address is the sentinel of the native asset of
+/// the given chain, whose balances are retrieved using `eth_getBalance`
+/// instead of the ERC-20 `balanceOf`.
+///
+/// The sentinel is the address of the native asset registered for the chain,
+/// if any, and [`NATIVE_TOKEN_ADDRESS`] otherwise.
+pub fn is_native_token(chain_id: u64, address: Address) -> bool {
+    let sentinel = NATIVE_TOKENS
+        .read()
+        .unwrap()
+        .get(&chain_id)
+        .map_or(NATIVE_TOKEN_ADDRESS, |token| token.address);
+
+    address == sentinel
+}
+
+/// Returns whether the given address is the sentinel of the native asset of
+/// any chain, the chain id being needed to tell if it's the native asset.
+pub(crate) fn is_native_sentinel(address: Address) -> bool {
+    address == NATIVE_TOKEN_ADDRESS
+        || NATIVE_TOKENS
+            .read()
+            .unwrap()
+            .values()
+            .any(|token| token.address == address)
+}
@@ -0,0 +1,173 @@
+use alloy::{
+    primitives::{Address, Bytes, B256, U256},
+    signers::{Signature, Signer},
+    sol,
+    sol_types::{Eip712Domain, SolCall, SolStruct},
+};
+
+sol!(
+    #[allow(clippy::too_many_arguments)]
+    #[sol(rpc)]
+    Erc2612Contract,
+    "abi/erc2612.json"
+);
+
+sol!(DaiPermitContract, "abi/dai_permit.json");
+
+sol! {
+    /// The [EIP-2612] `Permit` typed data.
+    ///
+    /// [EIP-2612]: https://eips.ethereum.org/EIPS/eip-2612
+    #[derive(Debug, PartialEq, Eq)]
+    struct Permit {
+        address owner;
+        address spender;
+        uint256 value;
+        uint256 nonce;
+        uint256 deadline;
+    }
+}
+
+mod dai {
+    alloy::sol! {
+        /// The `Permit` typed data used by DAI, which predates EIP-2612.
+        #[derive(Debug, PartialEq, Eq)]
+        struct Permit {
+            address holder;
+            address spender;
+            uint256 nonce;
+            uint256 expiry;
+            bool allowed;
+        }
+    }
+}
+
+pub use dai::Permit as DaiPermit;
+
+impl Permit {
+    /// Signs the permit for the given token domain.
+    pub async fn sign<S>(
+        &self,
+        signer: &S,
+        domain: &Eip712Domain,
+    ) -> Result<Signature, alloy::signers::Error>
+    where
+        S: Signer + Sync,
+    {
+        sign_struct(self, signer, domain).await
+    }
+
+    /// Encodes the `permit` call submitting this permit with the given
+    /// signature.
+    pub fn calldata(&self, signature: &Signature) -> Bytes {
+        let (v, r, s) = split(signature);
+
+        Erc2612Contract::permitCall {
+            owner: self.owner,
+            spender: self.spender,
+            value: self.value,
+            deadline: self.deadline,
+            v,
+            r,
+            s,
+        }
+        .abi_encode()
+        .into()
+    }
+}
+
+impl DaiPermit {
+    /// Signs the permit for the given token domain.
+    pub async fn sign<S>(
+        &self,
+        signer: &S,
+        domain: &Eip712Domain,
+    ) -> Result<Signature, alloy::signers::Error>
+    where
+        S: Signer + Sync,
+    {
+        sign_struct(self, signer, domain).await
+    }
+
+    /// Encodes the `permit` call submitting this permit with the given
+    /// signature.
+    pub fn calldata(&self, signature: &Signature) -> Bytes {
+        let (v, r, s) = split(signature);
+
+        DaiPermitContract::permitCall {
+            holder: self.holder,
+            spender: self.spender,
+            nonce: self.nonce,
+            expiry: self.expiry,
+            allowed: self.allowed,
+            v,
+            r,
+            s,
+        }
+        .abi_encode()
+        .into()
+    }
+}
+
+/// Signs the given EIP-712 typed data.
+pub(crate) async fn sign_struct<T, S>(
+    payload: &T,
+    signer: &S,
+    domain: &Eip712Domain,
+) -> Result<Signature, alloy::signers::Error>
+where
+    T: SolStruct,
+    S: Signer + Sync,
+{
+    signer.sign_hash(&payload.eip712_signing_hash(domain)).await
+}
+
+/// Splits the given signature into its `v`, `r` and `s` components, as
+/// expected by the `permit` functions.
+fn split(signature: &Signature) -> (u8, B256, B256) {
+    (
+        27 + signature.v() as u8,
+        B256::from(signature.r()),
+        B256::from(signature.s()),
+    )
+}
+
+/// Converts the result of an ERC-5267 `eip712Domain` call to an
+/// [`Eip712Domain`].
+pub(crate) fn domain_from_erc5267(domain: Erc2612Contract::eip712DomainReturn) -> Eip712Domain {
+    let fields = domain.fields[0];
+
+    Eip712Domain::new(
+        (fields & 0x01 != 0).then_some(domain.name.into()),
+        (fields & 0x02 != 0).then_some(domain.version.into()),
+        (fields & 0x04 != 0).then_some(domain.chainId),
+        (fields & 0x08 != 0).then_some(domain.verifyingContract),
+        (fields & 0x10 != 0).then_some(domain.salt),
+    )
+}
+
+/// Builds the candidate domains of a token not implementing ERC-5267, as
+/// most tokens use either `"1"` or `"2"` as their domain version.
+pub(crate) fn candidate_domains(name: String, chain_id: u64, token: Address) -> [Eip712Domain; 2] {
+    ["1", "2"].map(|version| {
+        Eip712Domain::new(
+            Some(name.clone().into()),
+            Some(version.into()),
+            Some(U256::from(chain_id)),
+            Some(token),
+            None,
+        )
+    })
+}
+
+/// Returns whether the given error of an `eip712Domain` call means that the
+/// token doesn't implement ERC-5267, as opposed to a failure of the node.
+pub(crate) fn is_unsupported(err: &alloy::contract::Error) -> bool {
+    match err {
+        alloy::contract::Error::ZeroData(..) | alloy::contract::Error::AbiError(_) => true,
+        alloy::contract::Error::TransportError(err) => err
+            .as_error_resp()
+            .is_some_and(|resp| resp.as_revert_data().is_some() || resp.message.contains("revert")),
+        _ => false,
+    }
+}
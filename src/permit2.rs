@@ -0,0 +1,351 @@
+use alloy::{
+    primitives::{
+        address,
+        aliases::{U160, U48},
+        Address, Bytes, U256,
+    },
+    signers::{Signature, Signer},
+    sol,
+    sol_types::{Eip712Domain, SolCall},
+};
+
+use crate::{error::InternalError, permit::sign_struct, Amount, Token, TokenId, TokenStore};
+
+/// The address Permit2 is deployed at on every chain.
+pub const PERMIT2_ADDRESS: Address = address!("000000000022D473030F116dDEE9F6B43aC78BA3");
+
+sol! {
+    #[sol(rpc)]
+    interface Permit2Contract {
+        struct PermitDetails {
+            address token;
+            uint160 amount;
+            uint48 expiration;
+            uint48 nonce;
+        }
+
+        struct PermitSingle {
+            PermitDetails details;
+            address spender;
+            uint256 sigDeadline;
+        }
+
+        struct PermitBatch {
+            PermitDetails[] details;
+            address spender;
+            uint256 sigDeadline;
+        }
+
+        struct TokenPermissions {
+            address token;
+            uint256 amount;
+        }
+
+        struct PermitTransferFrom {
+            TokenPermissions permitted;
+            uint256 nonce;
+            uint256 deadline;
+        }
+
+        struct SignatureTransferDetails {
+            address to;
+            uint256 requestedAmount;
+        }
+
+        function allowance(address user, address token, address spender)
+            external
+            view
+            returns (uint160 amount, uint48 expiration, uint48 nonce);
+
+        function permit(address owner, PermitSingle memory permitSingle, bytes calldata signature)
+            external;
+
+        function permit(address owner, PermitBatch memory permitBatch, bytes calldata signature)
+            external;
+
+        function permitTransferFrom(
+            PermitTransferFrom memory permit,
+            SignatureTransferDetails calldata transferDetails,
+            address owner,
+            bytes calldata signature
+        ) external;
+    }
+}
+
+mod typed_data {
+    alloy::sol! {
+        /// The allowance of a token, as signed in a Permit2 [`PermitSingle`]
+        /// or [`PermitBatch`].
+        #[derive(Debug, PartialEq, Eq)]
+        struct PermitDetails {
+            address token;
+            uint160 amount;
+            uint48 expiration;
+            uint48 nonce;
+        }
+
+        /// The Permit2 `PermitSingle` typed data, setting the allowance of a
+        /// single token.
+        #[derive(Debug, PartialEq, Eq)]
+        struct PermitSingle {
+            PermitDetails details;
+            address spender;
+            uint256 sigDeadline;
+        }
+
+        /// The Permit2 `PermitBatch` typed data, setting the allowances of
+        /// several tokens.
+        #[derive(Debug, PartialEq, Eq)]
+        struct PermitBatch {
+            PermitDetails[] details;
+            address spender;
+            uint256 sigDeadline;
+        }
+
+        /// A token amount, as signed in a Permit2 [`PermitTransferFrom`].
+        #[derive(Debug, PartialEq, Eq)]
+        struct TokenPermissions {
+            address token;
+            uint256 amount;
+        }
+
+        /// The Permit2 `PermitTransferFrom` typed data, allowing `spender` to
+        /// transfer tokens once.
+        #[derive(Debug, PartialEq, Eq)]
+        struct PermitTransferFrom {
+            TokenPermissions permitted;
+            address spender;
+            uint256 nonce;
+            uint256 deadline;
+        }
+    }
+}
+
+pub use typed_data::{
+    PermitBatch, PermitDetails, PermitSingle, PermitTransferFrom, TokenPermissions,
+};
+
+/// A Permit2 allowance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Permit2Allowance {
+    /// The allowed amount.
+    pub amount: U160,
+    /// The timestamp at which the allowance expires.
+    pub expiration: U48,
+    /// The nonce to use in the next signed permit.
+    pub nonce: U48,
+}
+
+impl From<Permit2Contract::allowanceReturn> for Permit2Allowance {
+    fn from(value: Permit2Contract::allowanceReturn) -> Self {
+        Self {
+            amount: value.amount,
+            expiration: value.expiration,
+            nonce: value.nonce,
+        }
+    }
+}
+
+/// Returns the EIP-712 domain of the Permit2 contract deployed at
+/// [`PERMIT2_ADDRESS`] on the given chain.
+pub fn permit2_domain(chain_id: u64) -> Eip712Domain {
+    Eip712Domain::new(
+        Some("Permit2".into()),
+        None,
+        Some(U256::from(chain_id)),
+        Some(PERMIT2_ADDRESS),
+        None,
+    )
+}
+
+impl PermitDetails {
+    /// Creates the allowance details of the given token, scaling `amount`
+    /// using the token decimals.
+    pub fn new(
+        token: &Token,
+        amount: impl Into<Amount>,
+        expiration: U48,
+        nonce: U48,
+    ) -> Result<Self, crate::Error> {
+        let value = amount
+            .into()
+            .to_raw(token.decimals)
+            .map_err(|err| crate::Error::new(token.address.into(), err))?;
+
+        let amount = U160::checked_from_limbs_slice(value.as_limbs()).ok_or_else(|| {
+            crate::Error::new(
+                token.address.into(),
+                InternalError::Overflow(token.get_balance(value)),
+            )
+        })?;
+
+        Ok(Self {
+            token: token.address,
+            amount,
+            expiration,
+            nonce,
+        })
+    }
+
+    /// Creates the allowance details of the token with the given id, looked
+    /// for in the given store, scaling `amount` using the token decimals.
+    pub fn from_store<'a, S>(
+        id: impl Into<TokenId>,
+        chain_id: u64,
+        store: &'a S,
+        amount: impl Into<Amount>,
+        expiration: U48,
+        nonce: U48,
+    ) -> Result<Self, crate::Error>
+    where
+        S: TokenStore<'a>,
+    {
+        let token = store.try_get(chain_id, id.into())?;
+
+        Self::new(&token, amount, expiration, nonce)
+    }
+
+    const fn to_call(&self) -> Permit2Contract::PermitDetails {
+        Permit2Contract::PermitDetails {
+            token: self.token,
+            amount: self.amount,
+            expiration: self.expiration,
+            nonce: self.nonce,
+        }
+    }
+}
+
+impl PermitSingle {
+    /// Signs the permit for the given Permit2 domain.
+    pub async fn sign<S>(
+        &self,
+        signer: &S,
+        domain: &Eip712Domain,
+    ) -> Result<Signature, alloy::signers::Error>
+    where
+        S: Signer + Sync,
+    {
+        sign_struct(self, signer, domain).await
+    }
+
+    /// Encodes the Permit2 `permit` call submitting this permit, signed by
+    /// `owner`.
+    pub fn calldata(&self, owner: Address, signature: &Signature) -> Bytes {
+        Permit2Contract::permit_0Call {
+            owner,
+            permitSingle: Permit2Contract::PermitSingle {
+                details: self.details.to_call(),
+                spender: self.spender,
+                sigDeadline: self.sigDeadline,
+            },
+            signature: signature.as_bytes().into(),
+        }
+        .abi_encode()
+        .into()
+    }
+}
+
+impl PermitBatch {
+    /// Signs the permit for the given Permit2 domain.
+    pub async fn sign<S>(
+        &self,
+        signer: &S,
+        domain: &Eip712Domain,
+    ) -> Result<Signature, alloy::signers::Error>
+    where
+        S: Signer + Sync,
+    {
+        sign_struct(self, signer, domain).await
+    }
+
+    /// Encodes the Permit2 `permit` call submitting this permit, signed by
+    /// `owner`.
+    pub fn calldata(&self, owner: Address, signature: &Signature) -> Bytes {
+        Permit2Contract::permit_1Call {
+            owner,
+            permitBatch: Permit2Contract::PermitBatch {
+                details: self.details.iter().map(PermitDetails::to_call).collect(),
+                spender: self.spender,
+                sigDeadline: self.sigDeadline,
+            },
+            signature: signature.as_bytes().into(),
+        }
+        .abi_encode()
+        .into()
+    }
+}
+
+impl TokenPermissions {
+    /// Creates the permissions over the given token, scaling `amount` using
+    /// the token decimals.
+    pub fn new(token: &Token, amount: impl Into<Amount>) -> Result<Self, crate::Error> {
+        let amount = amount
+            .into()
+            .to_raw(token.decimals)
+            .map_err(|err| crate::Error::new(token.address.into(), err))?;
+
+        Ok(Self {
+            token: token.address,
+            amount,
+        })
+    }
+
+    /// Creates the permissions over the token with the given id, looked for
+    /// in the given store, scaling `amount` using the token decimals.
+    pub fn from_store<'a, S>(
+        id: impl Into<TokenId>,
+        chain_id: u64,
+        store: &'a S,
+        amount: impl Into<Amount>,
+    ) -> Result<Self, crate::Error>
+    where
+        S: TokenStore<'a>,
+    {
+        let token = store.try_get(chain_id, id.into())?;
+
+        Self::new(&token, amount)
+    }
+}
+
+impl PermitTransferFrom {
+    /// Signs the permit for the given Permit2 domain.
+    pub async fn sign<S>(
+        &self,
+        signer: &S,
+        domain: &Eip712Domain,
+    ) -> Result<Signature, alloy::signers::Error>
+    where
+        S: Signer + Sync,
+    {
+        sign_struct(self, signer, domain).await
+    }
+
+    /// Encodes the Permit2 `permitTransferFrom` call, moving
+    /// `requested_amount` of the permitted tokens from `owner` to `to`.
+    pub fn calldata(
+        &self,
+        owner: Address,
+        to: Address,
+        requested_amount: U256,
+        signature: &Signature,
+    ) -> Bytes {
+        Permit2Contract::permitTransferFromCall {
+            permit: Permit2Contract::PermitTransferFrom {
+                permitted: Permit2Contract::TokenPermissions {
+                    token: self.permitted.token,
+                    amount: self.permitted.amount,
+                },
+                nonce: self.nonce,
+                deadline: self.deadline,
+            },
+            transferDetails: Permit2Contract::SignatureTransferDetails {
+                to,
+                requestedAmount: requested_amount,
+            },
+            owner,
+            signature: signature.as_bytes().into(),
+        }
+        .abi_encode()
+        .into()
+    }
+}
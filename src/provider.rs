@@ -1,7 +1,41 @@
-use alloy::{network::Network, primitives::Address, providers::Provider, sol};
+use crate::{
+    amount,
+    error::InternalError,
+    multicall::{
+        decode_result, decode_string_result, Multicall3, Multicall3Contract, MULTICALL3_ADDRESS,
+        MULTICALL3_BATCH_SIZE,
+    },
+    native::{is_native_sentinel, is_native_token, native_token},
+    permit::{candidate_domains, domain_from_erc5267, is_unsupported, Erc2612Contract},
+    permit2::{Permit2Contract, PERMIT2_ADDRESS},
+    safe::check_call,
+    stores::TokenStore,
+    Amount, ApprovalEvent, DaiPermit, Entry, Error, Permit, Permit2Allowance, StringEncoding,
+    Token, TokenAmount, TokenCapabilities, TokenDelta, TokenId, TokenMetadata, Transfer,
+    TransferEvent,
+};
+use alloy::{
+    consensus::BlockHeader,
+    eips::{BlockId, BlockNumberOrTag},
+    network::{BlockResponse, Network},
+    primitives::{Address, B256, U256},
+    providers::{PendingTransactionBuilder, Provider},
+    rpc::types::{Filter, Log},
+    signers::{Signature, Signer},
+    sol,
+    sol_types::{Eip712Domain, SolCall, SolEvent},
+    transports::{RpcError, TransportErrorKind, TransportResult},
+};
 use async_trait::async_trait;
 use bigdecimal::BigDecimal;
-use crate::{error::InternalError, stores::TokenStore, Entry, Error, Token, TokenId};
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use std::{collections::HashSet, sync::Arc};
+
+#[cfg(feature = "concurrent-store")]
+use crate::ConcurrentTokenStore;
 
 sol!(
     #[sol(rpc)]
@@ -9,35 +43,208 @@ sol!(
     "abi/erc20.json"
 );
 
+sol! {
+    interface Erc4626Contract {
+        function asset() external view returns (address);
+    }
+}
+
 /// Extends Alloy [`Provider`] trait with ERC-20 related features.
 #[async_trait]
 pub trait Erc20ProviderExt<N>: Provider<N> + Sized
 where
     N: Network,
 {
-    /// Retrieves a token by querying its ERC-20 contract.
+    /// Retrieves a token by querying its ERC-20 contract, along with its
+    /// metadata, see [`retrieve_metadata`](Self::retrieve_metadata), through
+    /// a single Multicall3 request.
+    ///
+    /// On chains without Multicall3, the token is retrieved through direct
+    /// calls to its contract, without its metadata.
+    ///
+    /// The native asset of the chain is returned as is, if known, see
+    /// [`native_token`](crate::native_token) and
+    /// [`register_native_token`](crate::register_native_token).
     async fn retrieve_token(&self, address: Address) -> Result<Token, Error> {
+        let chain_id = self
+            .get_chain_id()
+            .await
+            .map_err(|err| Error::new(address.into(), err))?;
+
+        retrieve_chain_token(self, chain_id, address).await
+    }
+
+    /// Retrieves the metadata of a token through a single Multicall3
+    /// request: its name, and the capabilities detected on its contract.
+    ///
+    /// A token exposing an EIP-712 `DOMAIN_SEPARATOR` is assumed to support
+    /// permits, and a token exposing an `asset` to be an ERC-4626 vault.
+    async fn retrieve_metadata(
+        &self,
+        address: Address,
+    ) -> Result<TokenMetadata, alloy::contract::Error> {
+        let results = Multicall3Contract::new(MULTICALL3_ADDRESS, self)
+            .aggregate3(metadata_calls(address).to_vec())
+            .call()
+            .await?
+            .returnData;
+
+        Ok(decode_metadata(&results))
+    }
+
+    /// Retrieves a token symbol, along with the [`StringEncoding`] used by
+    /// its ERC-20 contract to return it.
+    async fn retrieve_symbol(&self, address: Address) -> Result<(String, StringEncoding), Error> {
         let instance = Erc20Contract::Erc20ContractInstance::new(address, self);
 
-        let symbol = instance
+        let data = instance
             .symbol()
-            .call()
+            .call_raw()
             .await
             .map_err(|err| Error::new(address.into(), err))?;
 
-        let decimals = instance
-            .decimals()
-            .call()
+        StringEncoding::decode(&data).map_err(|err| Error::new(address.into(), err))
+    }
+
+    /// Retrieves a token name, along with the [`StringEncoding`] used by
+    /// its ERC-20 contract to return it.
+    async fn retrieve_name(&self, address: Address) -> Result<(String, StringEncoding), Error> {
+        let instance = Erc20Contract::Erc20ContractInstance::new(address, self);
+
+        let data = instance
+            .name()
+            .call_raw()
             .await
             .map_err(|err| Error::new(address.into(), err))?;
 
-        let token = Token::new(address, symbol._0, decimals._0);
+        StringEncoding::decode(&data).map_err(|err| Error::new(address.into(), err))
+    }
+
+    /// Retrieves several tokens at once, along with their metadata, by
+    /// aggregating the calls to their ERC-20 contracts through Multicall3.
+    ///
+    /// The tokens are the same as the ones of
+    /// [`retrieve_token`](Self::retrieve_token), and a token failing to be
+    /// retrieved doesn't make the others fail: the returned results are in
+    /// the same order as the given addresses. The calls are aggregated in
+    /// batches of at most 500, all made at the same block, and a failure of a
+    /// whole batch is reported for each of its tokens. Only a failure to know
+    /// the chain makes the whole retrieval fail.
+    async fn retrieve_tokens(
+        &self,
+        addresses: &[Address],
+    ) -> Result<Vec<Result<Token, Error>>, Error> {
+        if addresses.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // The sentinels of native assets are retrieved on their own
+        let contracts = addresses
+            .iter()
+            .enumerate()
+            .filter(|(_, address)| !is_native_sentinel(**address))
+            .map(|(index, address)| (index, *address))
+            .collect::<Vec<_>>();
+
+        // The calls of a token are made in the same batch, the first one
+        // also querying the chain id to be set in the tokens
+        let batches = contracts
+            .chunks((MULTICALL3_BATCH_SIZE - 1) / TOKEN_CALLS)
+            .collect::<Vec<_>>();
+
+        // The batches are made at the same block
+        let block = if batches.len() > 1 {
+            resolve_block(self, None)
+                .await
+                .map(BlockId::number)
+                .map_err(|err| batch_error(addresses, err))?
+        } else {
+            BlockId::latest()
+        };
+
+        let mut chain_id = None;
+        let mut results = addresses.iter().map(|_| None).collect::<Vec<_>>();
+
+        for (number, batch) in batches.iter().enumerate() {
+            let chain_id_call = (number == 0).then(|| Multicall3::Call3 {
+                target: MULTICALL3_ADDRESS,
+                allowFailure: true,
+                callData: Multicall3Contract::getChainIdCall {}.abi_encode().into(),
+            });
+
+            let calls = chain_id_call
+                .into_iter()
+                .chain(batch.iter().flat_map(|(_, address)| token_calls(*address)))
+                .collect::<Vec<_>>();
+
+            match Multicall3Contract::new(MULTICALL3_ADDRESS, self)
+                .aggregate3(calls)
+                .block(block)
+                .call()
+                .await
+            {
+                Ok(aggregated) => {
+                    let mut batch_results = aggregated.returnData.into_iter();
+
+                    if number == 0 {
+                        chain_id = batch_results
+                            .next()
+                            .and_then(|result| {
+                                decode_result::<Multicall3Contract::getChainIdCall>(&result).ok()
+                            })
+                            .and_then(|chain_id| u64::try_from(chain_id.chainid).ok());
+                    }
+
+                    for (index, _) in *batch {
+                        results[*index] = Some(Ok(batch_results
+                            .by_ref()
+                            .take(TOKEN_CALLS)
+                            .collect::<Vec<_>>()));
+                    }
+                }
+                Err(err) => {
+                    let err = Arc::new(err);
+
+                    for (index, _) in *batch {
+                        results[*index] = Some(Err(err.clone()));
+                    }
+                }
+            }
+        }
+
+        let chain_id = match chain_id {
+            Some(chain_id) => chain_id,
+            None => self
+                .get_chain_id()
+                .await
+                .map_err(|err| batch_error(addresses, err))?,
+        };
+
+        let mut tokens = Vec::with_capacity(addresses.len());
+
+        for (address, results) in addresses.iter().zip(results) {
+            let token = match results {
+                Some(Ok(results)) => decode_token(chain_id, *address, &results),
+                Some(Err(err)) => Err(Error::new(
+                    (*address).into(),
+                    InternalError::BatchFailed(err),
+                )),
+                // A sentinel is the native asset of the chain, or a token if
+                // it's the sentinel of another chain
+                None => retrieve_chain_token(self, chain_id, *address).await,
+            };
 
-        Ok(token)
+            tokens.push(token);
+        }
+
+        Ok(tokens)
     }
 
     /// Returns a token from the given store if present, otherwise retrieves
     /// it from its ERC-20 contract and update the store.
+    ///
+    /// A symbol shared by several tokens of the store, none of them being
+    /// canonical, fails with an ambiguity error.
     async fn get_token<'a, Id, S>(&'a self, id: Id, store: &'a mut S) -> Result<&'a Token, Error>
     where
         S: TokenStore<'a> + Send,
@@ -49,36 +256,824 @@ where
             .await
             .map_err(|err| Error::new(id.clone(), err))?;
 
+        // A symbol can't be retrieved, but can be missing or ambiguous
+        let unresolved = match id {
+            TokenId::Symbol(_) => store.resolve(chain_id, id.clone()).err(),
+            TokenId::Address(_) => None,
+        };
+
         match store.entry(chain_id, id.clone()) {
-            Entry::Occupied(e) => Ok(e.into_mut()),
+            Entry::Occupied(e) => Ok(e.into_mut().into_ref()),
             Entry::Vacant(e) => {
                 let token = match id.clone() {
-                    TokenId::Address(address) => self.retrieve_token(address).await,
-                    TokenId::Symbol(symbol) => {
-                        Err(Error::new(id, InternalError::NotInStore(symbol)))
+                    TokenId::Address(address) => {
+                        retrieve_chain_token(self, chain_id, address).await
                     }
+                    TokenId::Symbol(symbol) => Err(unresolved
+                        .unwrap_or_else(|| Error::new(id, InternalError::NotInStore(symbol)))),
                 }?;
 
-                Ok(e.insert(token))
+                Ok(e.insert(token).into_ref())
             }
         }
     }
 
+    /// Returns a token from the given shared store if present, otherwise
+    /// retrieves it from its ERC-20 contract and update the store.
+    ///
+    /// Concurrent calls for the same token share a single retrieval, and
+    /// the chain id is given rather than queried for the same reason.
+    #[cfg(feature = "concurrent-store")]
+    async fn get_shared_token<Id>(
+        &self,
+        chain_id: u64,
+        id: Id,
+        store: &ConcurrentTokenStore,
+    ) -> Result<Token, Error>
+    where
+        Id: Into<TokenId> + Send,
+    {
+        let id: TokenId = id.into();
+
+        store
+            .get_or_fetch(chain_id, id.clone(), || async {
+                match id.clone() {
+                    TokenId::Address(address) => {
+                        retrieve_chain_token(self, chain_id, address).await
+                    }
+                    TokenId::Symbol(symbol) => {
+                        Err(Error::new(id, InternalError::NotInStore(symbol)))
+                    }
+                }
+            })
+            .await
+    }
+
     /// Retrieves the given address balance from the given token contract.
     async fn balance_of(&self, token: Address, address: Address) -> Result<BigDecimal, Error> {
+        self.balance_of_at(token, address, BlockId::latest()).await
+    }
+
+    /// Retrieves the given address balance from the given token contract, at
+    /// the given block.
+    async fn balance_of_at(
+        &self,
+        token: Address,
+        address: Address,
+        block: BlockId,
+    ) -> Result<BigDecimal, Error> {
+        if let Some(native) = retrieve_native(self, token).await? {
+            let balance = self
+                .get_balance(address)
+                .block_id(block)
+                .await
+                .map_err(|err| Error::new(token.into(), err))?;
+
+            return Ok(native.get_balance(balance));
+        }
+
         let instance = Erc20Contract::Erc20ContractInstance::new(token, self);
 
+        // Only the decimals are needed to scale the balance, and they are
+        // retrieved along with it
+        let (result, decimals) = futures::try_join!(
+            async {
+                instance
+                    .balanceOf(address)
+                    .block(block)
+                    .call()
+                    .await
+                    .map_err(|err| Error::new(token.into(), err))
+            },
+            retrieve_decimals(self, token),
+        )?;
+
+        Ok(amount::to_decimal(result.balance, decimals))
+    }
+
+    /// Compares the balance of the given holder in the given token between
+    /// two blocks, returning its change from `from` to `to`.
+    async fn balance_diff<'a>(
+        &self,
+        token: &'a Token,
+        holder: Address,
+        from: BlockId,
+        to: BlockId,
+    ) -> Result<TokenDelta<'a>, Error> {
+        let native = is_native_token(token.chain_id, token.address);
+
+        let mut balances = [U256::ZERO; 2];
+
+        for (balance, block) in balances.iter_mut().zip([from, to]) {
+            *balance = if native {
+                self.get_balance(holder)
+                    .block_id(block)
+                    .await
+                    .map_err(|err| Error::new(token.address.into(), err))?
+            } else {
+                Erc20Contract::Erc20ContractInstance::new(token.address, self)
+                    .balanceOf(holder)
+                    .block(block)
+                    .call()
+                    .await
+                    .map_err(|err| Error::new(token.address.into(), err))?
+                    .balance
+            };
+        }
+
+        let [before, after] = balances;
+
+        TokenAmount::new(token, after).checked_delta(&TokenAmount::new(token, before))
+    }
+
+    /// Retrieves the balances of several holders in several tokens at once,
+    /// by aggregating the `balanceOf` calls through Multicall3.
+    ///
+    /// The tokens missing from the given store are retrieved and added to
+    /// it. The returned matrix has a row per holder and a column per token,
+    /// in the given orders, and a balance failing to be retrieved doesn't
+    /// make the whole batch fail.
+    ///
+    /// The balances of the native asset of the chain are retrieved through
+    /// the Multicall3 `getEthBalance` function. The calls are aggregated in
+    /// batches of at most 500, all made at the same block. A failure of a
+    /// whole batch is reported for the first token.
+    async fn balances<'a, S>(
+        &'a self,
+        holders: &[Address],
+        tokens: &[Address],
+        store: &'a mut S,
+    ) -> Result<Vec<Vec<Result<BigDecimal, Error>>>, Error>
+    where
+        S: TokenStore<'a> + Send + Sync,
+    {
+        self.balances_at(holders, tokens, BlockId::latest(), store)
+            .await
+    }
+
+    /// Retrieves the balances of several holders in several tokens at once,
+    /// at the given block.
+    ///
+    /// See [`Erc20ProviderExt::balances`].
+    async fn balances_at<'a, S>(
+        &'a self,
+        holders: &[Address],
+        tokens: &[Address],
+        block: BlockId,
+        store: &'a mut S,
+    ) -> Result<Vec<Vec<Result<BigDecimal, Error>>>, Error>
+    where
+        S: TokenStore<'a> + Send + Sync,
+    {
+        let chain_id = self
+            .get_chain_id()
+            .await
+            .map_err(|err| batch_error(tokens, err))?;
+
+        self.fill_store(chain_id, tokens.iter().copied().collect(), store)
+            .await?;
+
+        let store: &'a S = store;
+        let addresses = tokens;
+
+        let tokens = addresses
+            .iter()
+            .map(|address| {
+                store
+                    .get(chain_id, TokenId::Address(*address))
+                    .map(|token| token.clone())
+                    .ok_or(*address)
+            })
+            .collect::<Vec<_>>();
+
+        let calls = holders
+            .iter()
+            .flat_map(|holder| {
+                tokens.iter().flatten().map(|token| {
+                    if is_native_token(chain_id, token.address) {
+                        Multicall3::Call3 {
+                            target: MULTICALL3_ADDRESS,
+                            allowFailure: true,
+                            callData: Multicall3Contract::getEthBalanceCall { addr: *holder }
+                                .abi_encode()
+                                .into(),
+                        }
+                    } else {
+                        Multicall3::Call3 {
+                            target: token.address,
+                            allowFailure: true,
+                            callData: Erc20Contract::balanceOfCall { _owner: *holder }
+                                .abi_encode()
+                                .into(),
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // The batches are made at the same block, even if given by a tag
+        let block = match block {
+            BlockId::Number(tag) if calls.len() > MULTICALL3_BATCH_SIZE && !tag.is_number() => {
+                resolve_block(self, Some(&tag))
+                    .await
+                    .map(BlockId::number)
+                    .map_err(|err| batch_error(addresses, err))?
+            }
+            block => block,
+        };
+
+        let mut results = Vec::with_capacity(calls.len());
+
+        for batch in calls.chunks(MULTICALL3_BATCH_SIZE) {
+            let batch = Multicall3Contract::new(MULTICALL3_ADDRESS, self)
+                .aggregate3(batch.to_vec())
+                .block(block)
+                .call()
+                .await
+                .map_err(|err| batch_error(addresses, err))?;
+
+            results.extend(batch.returnData);
+        }
+
+        let mut results = results.into_iter();
+
+        let mut balances = Vec::with_capacity(holders.len());
+
+        for _ in holders {
+            let mut row = Vec::with_capacity(tokens.len());
+
+            for token in &tokens {
+                let balance = match token {
+                    Ok(token) => {
+                        let result = results.next();
+
+                        let balance = if is_native_token(chain_id, token.address) {
+                            result
+                                .ok_or(InternalError::CallFailed(
+                                    Multicall3Contract::getEthBalanceCall::SIGNATURE,
+                                ))
+                                .and_then(|result| {
+                                    decode_result::<Multicall3Contract::getEthBalanceCall>(&result)
+                                })
+                                .map(|result| result.balance)
+                        } else {
+                            result
+                                .ok_or(InternalError::CallFailed(
+                                    Erc20Contract::balanceOfCall::SIGNATURE,
+                                ))
+                                .and_then(|result| {
+                                    decode_result::<Erc20Contract::balanceOfCall>(&result)
+                                })
+                                .map(|result| result.balance)
+                        };
+
+                        balance
+                            .map(|balance| token.get_balance(balance))
+                            .map_err(|err| Error::new(token.address.into(), err))
+                    }
+                    Err(address) => Err(Error::new(
+                        (*address).into(),
+                        InternalError::NotInStore(address.to_string()),
+                    )),
+                };
+
+                row.push(balance);
+            }
+
+            balances.push(row);
+        }
+
+        Ok(balances)
+    }
+
+    /// Moves `amount` of the given token from the caller's account to `to`.
+    async fn transfer<A>(
+        &self,
+        token: Address,
+        to: Address,
+        amount: A,
+    ) -> Result<PendingTransactionBuilder<N>, Error>
+    where
+        A: Into<Amount> + Send,
+    {
+        let instance = Erc20Contract::Erc20ContractInstance::new(token, self);
+        let amount = to_raw_amount(self, token, amount.into()).await?;
+
+        instance
+            .transfer(to, amount)
+            .send()
+            .await
+            .map_err(|err| Error::new(token.into(), err))
+    }
+
+    /// Sets `amount` as the allowance of `spender` over the caller's tokens.
+    async fn approve<A>(
+        &self,
+        token: Address,
+        spender: Address,
+        amount: A,
+    ) -> Result<PendingTransactionBuilder<N>, Error>
+    where
+        A: Into<Amount> + Send,
+    {
+        let instance = Erc20Contract::Erc20ContractInstance::new(token, self);
+        let amount = to_raw_amount(self, token, amount.into()).await?;
+
+        instance
+            .approve(spender, amount)
+            .send()
+            .await
+            .map_err(|err| Error::new(token.into(), err))
+    }
+
+    /// Moves `amount` of the given token from `from` to `to` using the
+    /// allowance mechanism.
+    async fn transfer_from<A>(
+        &self,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: A,
+    ) -> Result<PendingTransactionBuilder<N>, Error>
+    where
+        A: Into<Amount> + Send,
+    {
+        let instance = Erc20Contract::Erc20ContractInstance::new(token, self);
+        let amount = to_raw_amount(self, token, amount.into()).await?;
+
+        instance
+            .transferFrom(from, to, amount)
+            .send()
+            .await
+            .map_err(|err| Error::new(token.into(), err))
+    }
+
+    /// Moves `amount` of the given token from the caller's account to `to`,
+    /// after having simulated the call to make sure it succeeds.
+    ///
+    /// Tokens not returning any boolean (like USDT) are supported, while an
+    /// address without code fails.
+    async fn safe_transfer<A>(
+        &self,
+        token: Address,
+        to: Address,
+        amount: A,
+    ) -> Result<PendingTransactionBuilder<N>, Error>
+    where
+        A: Into<Amount> + Send,
+    {
+        let instance = Erc20Contract::Erc20ContractInstance::new(token, self);
+        let amount = to_raw_amount(self, token, amount.into()).await?;
+        let call = instance.transfer(to, amount);
+
+        check_call::<Erc20Contract::transferCall, _, _>(self, token, call.call_raw().await)
+            .await
+            .map_err(|err| Error::new(token.into(), err))?;
+
+        call.send()
+            .await
+            .map_err(|err| Error::new(token.into(), err))
+    }
+
+    /// Sets `amount` as the allowance of `spender` over the caller's tokens,
+    /// after having simulated the call to make sure it succeeds.
+    ///
+    /// Tokens not returning any boolean (like USDT) are supported, while an
+    /// address without code fails.
+    async fn safe_approve<A>(
+        &self,
+        token: Address,
+        spender: Address,
+        amount: A,
+    ) -> Result<PendingTransactionBuilder<N>, Error>
+    where
+        A: Into<Amount> + Send,
+    {
+        let instance = Erc20Contract::Erc20ContractInstance::new(token, self);
+        let amount = to_raw_amount(self, token, amount.into()).await?;
+        let call = instance.approve(spender, amount);
+
+        check_call::<Erc20Contract::approveCall, _, _>(self, token, call.call_raw().await)
+            .await
+            .map_err(|err| Error::new(token.into(), err))?;
+
+        call.send()
+            .await
+            .map_err(|err| Error::new(token.into(), err))
+    }
+
+    /// Moves `amount` of the given token from `from` to `to` using the
+    /// allowance mechanism, after having simulated the call to make sure it
+    /// succeeds.
+    ///
+    /// Tokens not returning any boolean (like USDT) are supported, while an
+    /// address without code fails.
+    async fn safe_transfer_from<A>(
+        &self,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: A,
+    ) -> Result<PendingTransactionBuilder<N>, Error>
+    where
+        A: Into<Amount> + Send,
+    {
+        let instance = Erc20Contract::Erc20ContractInstance::new(token, self);
+        let amount = to_raw_amount(self, token, amount.into()).await?;
+        let call = instance.transferFrom(from, to, amount);
+
+        check_call::<Erc20Contract::transferFromCall, _, _>(self, token, call.call_raw().await)
+            .await
+            .map_err(|err| Error::new(token.into(), err))?;
+
+        call.send()
+            .await
+            .map_err(|err| Error::new(token.into(), err))
+    }
+
+    /// Returns the current EIP-2612 nonce of `owner` for the given token.
+    async fn permit_nonce(&self, token: Address, owner: Address) -> Result<U256, Error> {
+        let instance = Erc2612Contract::new(token, self);
+
         let result = instance
-            .balanceOf(address)
+            .nonces(owner)
             .call()
             .await
-            .map_err(|err| Error::new(address.into(), err))?;
+            .map_err(|err| Error::new(token.into(), err))?;
 
-        let token = self.retrieve_token(token).await?;
+        Ok(result._0)
+    }
 
-        let balance = token.get_balance(result.balance);
+    /// Returns the EIP-712 domain separator of the given token.
+    async fn domain_separator(&self, token: Address) -> Result<B256, Error> {
+        let instance = Erc2612Contract::new(token, self);
+
+        let result = instance
+            .DOMAIN_SEPARATOR()
+            .call()
+            .await
+            .map_err(|err| Error::new(token.into(), err))?;
 
-        Ok(balance)
+        Ok(result._0)
+    }
+
+    /// Returns the EIP-712 domain of the given token.
+    ///
+    /// The domain is queried through ERC-5267 if the token supports it,
+    /// otherwise it is rebuilt from the token name and checked against its
+    /// domain separator. Only a reverted `eip712Domain` call falls back to
+    /// the rebuilt domain, other failures being returned.
+    async fn eip712_domain(&self, token: Address) -> Result<Eip712Domain, Error> {
+        let instance = Erc2612Contract::new(token, self);
+
+        match instance.eip712Domain().call().await {
+            Ok(domain) => return Ok(domain_from_erc5267(domain)),
+            Err(err) if !is_unsupported(&err) => return Err(Error::new(token.into(), err)),
+            Err(_) => {}
+        }
+
+        let (name, _) = self.retrieve_name(token).await?;
+        let domain_separator = self.domain_separator(token).await?;
+        let chain_id = self
+            .get_chain_id()
+            .await
+            .map_err(|err| Error::new(token.into(), err))?;
+
+        candidate_domains(name, chain_id, token)
+            .into_iter()
+            .find(|domain| domain.separator() == domain_separator)
+            .ok_or_else(|| Error::new(token.into(), InternalError::UnknownDomain))
+    }
+
+    /// Builds and signs an EIP-2612 permit allowing `spender` to spend
+    /// `amount` of the signer's tokens until `deadline`.
+    async fn sign_permit<S, A>(
+        &self,
+        token: Address,
+        signer: &S,
+        spender: Address,
+        amount: A,
+        deadline: U256,
+    ) -> Result<(Permit, Signature), Error>
+    where
+        S: Signer + Send + Sync,
+        A: Into<Amount> + Send,
+    {
+        let owner = signer.address();
+
+        let permit = Permit {
+            owner,
+            spender,
+            value: to_raw_amount(self, token, amount.into()).await?,
+            nonce: self.permit_nonce(token, owner).await?,
+            deadline,
+        };
+
+        let domain = self.eip712_domain(token).await?;
+
+        let signature = permit
+            .sign(signer, &domain)
+            .await
+            .map_err(|err| Error::new(token.into(), err))?;
+
+        Ok((permit, signature))
+    }
+
+    /// Builds and signs a DAI-style permit allowing (or disallowing)
+    /// `spender` to spend all of the signer's tokens until `expiry`.
+    async fn sign_dai_permit<S>(
+        &self,
+        token: Address,
+        signer: &S,
+        spender: Address,
+        allowed: bool,
+        expiry: U256,
+    ) -> Result<(DaiPermit, Signature), Error>
+    where
+        S: Signer + Send + Sync,
+    {
+        let holder = signer.address();
+
+        let permit = DaiPermit {
+            holder,
+            spender,
+            nonce: self.permit_nonce(token, holder).await?,
+            expiry,
+            allowed,
+        };
+
+        let domain = self.eip712_domain(token).await?;
+
+        let signature = permit
+            .sign(signer, &domain)
+            .await
+            .map_err(|err| Error::new(token.into(), err))?;
+
+        Ok((permit, signature))
+    }
+
+    /// Returns the Permit2 allowance of `spender` over the `owner`'s tokens.
+    async fn permit2_allowance(
+        &self,
+        token: Address,
+        owner: Address,
+        spender: Address,
+    ) -> Result<Permit2Allowance, Error> {
+        let instance = Permit2Contract::new(PERMIT2_ADDRESS, self);
+
+        let result = instance
+            .allowance(owner, token, spender)
+            .call()
+            .await
+            .map_err(|err| Error::new(token.into(), err))?;
+
+        Ok(result.into())
+    }
+
+    /// Returns the logs matching the given filter, splitting its block
+    /// range in smaller chunks when the node refuses to query it at once
+    /// because it's too wide or returns too many logs.
+    ///
+    /// The block tags of the range are resolved first, a missing bound being
+    /// the latest block. A failure is reported for the first address of the
+    /// filter.
+    async fn get_logs_chunked(&self, filter: &Filter) -> Result<Vec<Log>, Error> {
+        let addresses = filter.address.iter().copied().collect::<Vec<_>>();
+
+        if filter.get_block_hash().is_some() {
+            return self
+                .get_logs(filter)
+                .await
+                .map_err(|err| batch_error(&addresses, err));
+        }
+
+        let from_block = resolve_block(self, filter.block_option.get_from_block())
+            .await
+            .map_err(|err| batch_error(&addresses, err))?;
+        let to_block = resolve_block(self, filter.block_option.get_to_block())
+            .await
+            .map_err(|err| batch_error(&addresses, err))?;
+
+        let mut logs = Vec::new();
+        let mut ranges = vec![(from_block, to_block, 0)];
+
+        while let Some((from_block, to_block, depth)) = ranges.pop() {
+            let chunk_filter = filter.clone().from_block(from_block).to_block(to_block);
+
+            match self.get_logs(&chunk_filter).await {
+                Ok(chunk) => logs.extend(chunk),
+                Err(err)
+                    if is_range_error(&err) && from_block < to_block && depth < MAX_SPLIT_DEPTH =>
+                {
+                    let middle = from_block + (to_block - from_block) / 2;
+
+                    ranges.push((middle + 1, to_block, depth + 1));
+                    ranges.push((from_block, middle, depth + 1));
+                }
+                Err(err) => return Err(batch_error(&addresses, err)),
+            }
+        }
+
+        Ok(logs)
+    }
+
+    /// Returns the `Transfer` events matching the given filter, along with
+    /// their tokens.
+    ///
+    /// The tokens missing from the given store are retrieved and added to
+    /// it. Events emitted by contracts that can't be retrieved as ERC-20
+    /// tokens are ignored.
+    async fn get_transfer_events<'a, S>(
+        &'a self,
+        filter: Filter,
+        store: &'a mut S,
+    ) -> Result<Vec<TransferEvent>, Error>
+    where
+        S: TokenStore<'a> + Send + Sync,
+    {
+        let chain_id = self
+            .get_chain_id()
+            .await
+            .map_err(|err| batch_error(&[], err))?;
+        let filter = filter.event_signature(Erc20Contract::Transfer::SIGNATURE_HASH);
+        let logs = self.get_logs_chunked(&filter).await?;
+
+        self.fill_store(chain_id, logs.iter().map(Log::address).collect(), store)
+            .await?;
+
+        let store: &'a S = store;
+
+        Ok(logs
+            .iter()
+            .filter_map(|log| TransferEvent::from_store(log, chain_id, store))
+            .collect())
+    }
+
+    /// Returns the `Approval` events matching the given filter, along with
+    /// their tokens.
+    ///
+    /// The tokens missing from the given store are retrieved and added to
+    /// it. Events emitted by contracts that can't be retrieved as ERC-20
+    /// tokens are ignored.
+    async fn get_approval_events<'a, S>(
+        &'a self,
+        filter: Filter,
+        store: &'a mut S,
+    ) -> Result<Vec<ApprovalEvent>, Error>
+    where
+        S: TokenStore<'a> + Send + Sync,
+    {
+        let chain_id = self
+            .get_chain_id()
+            .await
+            .map_err(|err| batch_error(&[], err))?;
+        let filter = filter.event_signature(Erc20Contract::Approval::SIGNATURE_HASH);
+        let logs = self.get_logs_chunked(&filter).await?;
+
+        self.fill_store(chain_id, logs.iter().map(Log::address).collect(), store)
+            .await?;
+
+        let store: &'a S = store;
+
+        Ok(logs
+            .iter()
+            .filter_map(|log| ApprovalEvent::from_store(log, chain_id, store))
+            .collect())
+    }
+
+    /// Returns a stream of the incoming and outgoing transfers of the given
+    /// holders, optionally restricted to the given tokens, by polling a logs
+    /// filter.
+    ///
+    /// The tokens missing from the given store are retrieved and added to
+    /// it. Transfers removed by a chain reorganization are yielded again,
+    /// with their `removed` flag set.
+    ///
+    /// No holders, or no tokens, means no transfers rather than the
+    /// transfers of everyone: the returned stream is then empty.
+    ///
+    /// When the tokens of some transfers fail to be retrieved, the error is
+    /// yielded after the other transfers, and these transfers are retried
+    /// on the next poll. At most 10,000 transfers are kept to be retried, so
+    /// that a lasting failure doesn't grow the memory without bound: beyond
+    /// it, the oldest ones are dropped.
+    async fn watch_transfers<S>(
+        &self,
+        holders: Vec<Address>,
+        tokens: Option<Vec<Address>>,
+        store: S,
+    ) -> TransportResult<BoxStream<'static, Result<TransferEvent, Error>>>
+    where
+        Self: Clone + 'static,
+        S: for<'b> TokenStore<'b> + Send + Sync + 'static,
+    {
+        // An empty topic matches any holder, and no address any token
+        if holders.is_empty() || tokens.as_ref().is_some_and(Vec::is_empty) {
+            return Ok(stream::empty().boxed());
+        }
+
+        let chain_id = self.get_chain_id().await?;
+
+        let mut filter = Filter::new().event_signature(Erc20Contract::Transfer::SIGNATURE_HASH);
+
+        if let Some(tokens) = tokens {
+            filter = filter.address(tokens);
+        }
+
+        let topics = holders
+            .iter()
+            .map(|holder| holder.into_word())
+            .collect::<Vec<_>>();
+
+        let outgoing = self
+            .watch_logs(&filter.clone().topic1(topics.clone()))
+            .await?;
+        let incoming = self.watch_logs(&filter.topic2(topics)).await?;
+
+        // The transfers between two holders are yielded by both filters, so
+        // they are only kept from the outgoing one.
+        let incoming = incoming.into_stream().map(move |logs| {
+            logs.into_iter()
+                .filter(|log| Transfer::decode_log(log).is_some_and(|t| !holders.contains(&t.from)))
+                .collect::<Vec<_>>()
+        });
+
+        let logs = stream::select(outgoing.into_stream(), incoming);
+
+        // The logs whose tokens failed to be retrieved are kept, to be
+        // retried along with the next ones.
+        let events = stream::unfold(
+            (logs, self.clone(), store, Vec::new()),
+            move |(mut stream, provider, mut store, mut pending)| async move {
+                let mut logs = std::mem::take(&mut pending);
+                logs.extend(stream.next().await?);
+
+                let result = provider
+                    .fill_store(
+                        chain_id,
+                        logs.iter().map(Log::address).collect(),
+                        &mut store,
+                    )
+                    .await;
+
+                let mut events = Vec::with_capacity(logs.len());
+
+                for log in logs {
+                    if result.is_err() && !store.contains(chain_id, TokenId::Address(log.address()))
+                    {
+                        pending.push(log);
+                    } else if let Some(event) = TransferEvent::from_store(&log, chain_id, &store) {
+                        events.push(Ok(event));
+                    }
+                }
+
+                if let Err(err) = result {
+                    events.push(Err(err));
+                }
+
+                if pending.len() > MAX_PENDING_LOGS {
+                    pending.drain(..pending.len() - MAX_PENDING_LOGS);
+                }
+
+                Some((stream::iter(events), (stream, provider, store, pending)))
+            },
+        )
+        .flatten();
+
+        Ok(events.boxed())
+    }
+
+    /// Retrieves the given tokens missing from the store, and adds them to
+    /// it. Tokens failing to be retrieved are ignored, unless their batch of
+    /// calls failed as a whole: the first such failure is then returned, once
+    /// the other tokens are added.
+    async fn fill_store<'a, S>(
+        &self,
+        chain_id: u64,
+        addresses: HashSet<Address>,
+        store: &mut S,
+    ) -> Result<(), Error>
+    where
+        S: TokenStore<'a> + Send,
+    {
+        let missing = addresses
+            .into_iter()
+            .filter(|address| !store.contains(chain_id, TokenId::Address(*address)))
+            .collect::<Vec<_>>();
+
+        let mut failed = None;
+
+        for token in self.retrieve_tokens(&missing).await? {
+            match token {
+                Ok(token) => store.insert(chain_id, token),
+                Err(err) if matches!(err.source, InternalError::BatchFailed(_)) => {
+                    failed.get_or_insert(err);
+                }
+                Err(_) => {}
+            }
+        }
+
+        failed.map_or(Ok(()), Err)
     }
 }
 
@@ -89,3 +1084,253 @@ where
     N: Network,
 {
 }
+
+/// Converts the given amount to the token smallest unit, only retrieving the
+/// token decimals if needed.
+async fn to_raw_amount<P, N>(provider: &P, token: Address, amount: Amount) -> Result<U256, Error>
+where
+    P: Erc20ProviderExt<N>,
+    N: Network,
+{
+    let decimals = match amount {
+        Amount::Raw(amount) => return Ok(amount),
+        Amount::Decimal(_) => retrieve_decimals(provider, token).await?,
+    };
+
+    amount
+        .to_raw(decimals)
+        .map_err(|err| Error::new(token.into(), err))
+}
+
+/// Returns the native asset of the chain if the given address is its
+/// sentinel, only querying the chain id for the sentinels of native assets.
+async fn retrieve_native<P, N>(provider: &P, address: Address) -> Result<Option<Token>, Error>
+where
+    P: Erc20ProviderExt<N>,
+    N: Network,
+{
+    if !is_native_sentinel(address) {
+        return Ok(None);
+    }
+
+    let chain_id = provider
+        .get_chain_id()
+        .await
+        .map_err(|err| Error::new(address.into(), err))?;
+
+    if !is_native_token(chain_id, address) {
+        return Ok(None);
+    }
+
+    native_token(chain_id)
+        .map(Some)
+        .ok_or_else(|| Error::new(address.into(), InternalError::UnknownNativeToken(chain_id)))
+}
+
+/// Retrieves the decimals of the given token, without the rest of it.
+async fn retrieve_decimals<P, N>(provider: &P, token: Address) -> Result<u8, Error>
+where
+    P: Erc20ProviderExt<N>,
+    N: Network,
+{
+    if let Some(native) = retrieve_native(provider, token).await? {
+        return Ok(native.decimals);
+    }
+
+    Ok(Erc20Contract::Erc20ContractInstance::new(token, provider)
+        .decimals()
+        .call()
+        .await
+        .map_err(|err| Error::new(token.into(), err))?
+        ._0)
+}
+
+/// The maximum number of transfer logs kept by
+/// [`Erc20ProviderExt::watch_transfers`] to be retried.
+const MAX_PENDING_LOGS: usize = 10_000;
+
+/// The maximum number of times the block range of a logs query is halved,
+/// before giving up.
+const MAX_SPLIT_DEPTH: u32 = 12;
+
+/// Returns the number of the given block, the latest one if missing.
+async fn resolve_block<P, N>(provider: &P, block: Option<&BlockNumberOrTag>) -> TransportResult<u64>
+where
+    P: Provider<N>,
+    N: Network,
+{
+    match block {
+        Some(BlockNumberOrTag::Number(number)) => Ok(*number),
+        Some(BlockNumberOrTag::Earliest) => Ok(0),
+        Some(tag @ (BlockNumberOrTag::Safe | BlockNumberOrTag::Finalized)) => provider
+            .get_block_by_number(*tag)
+            .await?
+            .map(|block| block.header().number())
+            .ok_or_else(|| RpcError::local_usage_str(&format!("The {tag} block is unknown"))),
+        Some(BlockNumberOrTag::Latest | BlockNumberOrTag::Pending) | None => {
+            provider.get_block_number().await
+        }
+    }
+}
+
+/// Returns whether the given error means that a logs query covers too many
+/// blocks or returns too many logs, as opposed to any other failure.
+///
+/// The messages of the main node providers are matched, rather than the
+/// `-32005` code of EIP-1474, also used for rate limiting: splitting the
+/// range of a throttled query would only make more requests.
+fn is_range_error(err: &RpcError<TransportErrorKind>) -> bool {
+    err.as_error_resp().is_some_and(|resp| {
+        let message = resp.message.to_lowercase();
+
+        let rate_limited = ["rate limit", "too many requests", "request limit"]
+            .iter()
+            .any(|pattern| message.contains(pattern));
+
+        !rate_limited
+            && [
+                "block range",
+                "query returned more than",
+                "response size",
+                "too many results",
+                "too many blocks",
+            ]
+            .iter()
+            .any(|pattern| message.contains(pattern))
+    })
+}
+
+/// Creates an [`Error`] about a batch of tokens, reported for its first token,
+/// or for the zero address when it's empty.
+fn batch_error<E>(addresses: &[Address], err: E) -> Error
+where
+    E: Into<InternalError>,
+{
+    let address = addresses.first().copied().unwrap_or_default();
+
+    Error::new(address.into(), err)
+}
+
+/// Retrieves a token of the given chain, see
+/// [`Erc20ProviderExt::retrieve_token`].
+pub(crate) async fn retrieve_chain_token<P, N>(
+    provider: &P,
+    chain_id: u64,
+    address: Address,
+) -> Result<Token, Error>
+where
+    P: Erc20ProviderExt<N>,
+    N: Network,
+{
+    if is_native_token(chain_id, address) {
+        return native_token(chain_id).ok_or_else(|| {
+            Error::new(address.into(), InternalError::UnknownNativeToken(chain_id))
+        });
+    }
+
+    // The token is retrieved along with its metadata in a single request
+    let aggregated = Multicall3Contract::new(MULTICALL3_ADDRESS, provider)
+        .aggregate3(token_calls(address).to_vec())
+        .call()
+        .await;
+
+    if let Ok(results) = aggregated {
+        return decode_token(chain_id, address, &results.returnData);
+    }
+
+    // Multicall3 may not be deployed, like on a devnet, so the token is
+    // retrieved through its own contract, without its metadata
+    let (symbol, _) = provider.retrieve_symbol(address).await?;
+
+    let decimals = Erc20Contract::Erc20ContractInstance::new(address, provider)
+        .decimals()
+        .call()
+        .await
+        .map_err(|err| Error::new(address.into(), err))?;
+
+    Ok(Token::new(chain_id, address, symbol, decimals._0))
+}
+
+/// The number of calls aggregated to retrieve a token, see [`token_calls`].
+const TOKEN_CALLS: usize = 5;
+
+/// Returns the calls aggregated to retrieve a token along with its metadata.
+fn token_calls(address: Address) -> [Multicall3::Call3; TOKEN_CALLS] {
+    let call = |call_data: Vec<u8>| Multicall3::Call3 {
+        target: address,
+        allowFailure: true,
+        callData: call_data.into(),
+    };
+
+    let [name, domain_separator, asset] = metadata_calls(address);
+
+    [
+        call(Erc20Contract::symbolCall {}.abi_encode()),
+        call(Erc20Contract::decimalsCall {}.abi_encode()),
+        name,
+        domain_separator,
+        asset,
+    ]
+}
+
+/// Decodes a token from the results of its [`token_calls`].
+///
+/// The token only fails if its symbol or decimals can't be decoded, its
+/// metadata being retrieved on a best-effort basis.
+fn decode_token(
+    chain_id: u64,
+    address: Address,
+    results: &[Multicall3::Result],
+) -> Result<Token, Error> {
+    let [symbol, decimals, metadata @ ..] = results else {
+        return Err(Error::new(
+            address.into(),
+            InternalError::CallFailed(Erc20Contract::symbolCall::SIGNATURE),
+        ));
+    };
+
+    let (symbol, _) = decode_string_result::<Erc20Contract::symbolCall>(symbol)
+        .map_err(|err| Error::new(address.into(), err))?;
+    let decimals = decode_result::<Erc20Contract::decimalsCall>(decimals)
+        .map_err(|err| Error::new(address.into(), err))?;
+
+    Ok(Token::new(chain_id, address, symbol, decimals._0).with_metadata(decode_metadata(metadata)))
+}
+
+/// Returns the calls aggregated to retrieve the metadata of a token, see
+/// [`Erc20ProviderExt::retrieve_metadata`].
+fn metadata_calls(address: Address) -> [Multicall3::Call3; 3] {
+    let call = |call_data: Vec<u8>| Multicall3::Call3 {
+        target: address,
+        allowFailure: true,
+        callData: call_data.into(),
+    };
+
+    [
+        call(Erc20Contract::nameCall {}.abi_encode()),
+        call(Erc2612Contract::DOMAIN_SEPARATORCall {}.abi_encode()),
+        call(Erc4626Contract::assetCall {}.abi_encode()),
+    ]
+}
+
+/// Decodes the metadata of a token from the results of its
+/// [`metadata_calls`].
+fn decode_metadata(results: &[Multicall3::Result]) -> TokenMetadata {
+    TokenMetadata {
+        name: results
+            .first()
+            .and_then(|result| decode_string_result::<Erc20Contract::nameCall>(result).ok())
+            .map(|(name, _)| name),
+        logo_uri: None,
+        tags: Vec::new(),
+        capabilities: TokenCapabilities {
+            permit: results.get(1).is_some_and(|result| {
+                decode_result::<Erc2612Contract::DOMAIN_SEPARATORCall>(result).is_ok()
+            }),
+            erc4626: results
+                .get(2)
+                .is_some_and(|result| decode_result::<Erc4626Contract::assetCall>(result).is_ok()),
+            fee_on_transfer: false,
+        },
+    }
+}
@@ -0,0 +1,73 @@
+use alloy::{
+    network::Network,
+    primitives::{Address, Bytes},
+    providers::Provider,
+    sol_types::{decode_revert_reason, SolCall, SolValue},
+};
+
+use crate::error::InternalError;
+
+/// Checks the raw result of a simulated `transfer`, `approve` or
+/// `transferFrom` call to the given token, the same way OpenZeppelin's
+/// `SafeERC20` does: an empty return data is a success, for tokens (like
+/// USDT) not returning any boolean, as long as the token has some code.
+pub(crate) async fn check_call<C, P, N>(
+    provider: &P,
+    token: Address,
+    result: Result<Bytes, alloy::contract::Error>,
+) -> Result<(), InternalError>
+where
+    C: SolCall,
+    P: Provider<N>,
+    N: Network,
+{
+    let empty = matches!(&result, Ok(data) if data.is_empty());
+
+    check_return::<C>(result)?;
+
+    // Calling an address without code succeeds, returning nothing
+    if empty && provider.get_code_at(token).await?.is_empty() {
+        return Err(InternalError::NoCode(C::SIGNATURE));
+    }
+
+    Ok(())
+}
+
+/// Checks the raw result of a simulated call, see [`check_call`].
+pub(crate) fn check_return<C: SolCall>(
+    result: Result<Bytes, alloy::contract::Error>,
+) -> Result<(), InternalError> {
+    match result {
+        Ok(data) if data.is_empty() => Ok(()),
+        Ok(data) => match bool::abi_decode(&data, true)? {
+            true => Ok(()),
+            false => Err(InternalError::ReturnedFalse(C::SIGNATURE)),
+        },
+        Err(err) => match err.as_revert_data() {
+            Some(data) => Err(InternalError::Reverted(
+                C::SIGNATURE,
+                decode_revert_reason(&data).unwrap_or_else(|| data.to_string()),
+            )),
+            None => Err(err.into()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::Bytes, sol_types::SolValue};
+
+    use crate::{error::InternalError, provider::Erc20Contract::transferCall};
+
+    use super::check_return;
+
+    #[test]
+    fn test_check_return() {
+        assert!(check_return::<transferCall>(Ok(Bytes::new())).is_ok());
+        assert!(check_return::<transferCall>(Ok(true.abi_encode().into())).is_ok());
+        assert!(matches!(
+            check_return::<transferCall>(Ok(false.abi_encode().into())),
+            Err(InternalError::ReturnedFalse(_))
+        ));
+    }
+}
@@ -4,12 +4,19 @@ use alloy::primitives::Address;
 
 use crate::{token_id::TokenId, Token};
 
-use super::TokenStore;
+use super::{StoreSnapshot, TokenMut, TokenStore, TokenStoreMut};
 
 /// A basic [`TokenStore`] implementation.
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "StoreSnapshot", into = "StoreSnapshot")
+)]
 pub struct BasicTokenStore {
-    tokens: HashMap<(u64, TokenId), Token>,
+    tokens: HashMap<(u64, Address), Token>,
+    symbols: HashMap<(u64, String), Vec<Address>>,
+    canonical: HashMap<(u64, String), Address>,
 }
 
 impl BasicTokenStore {
@@ -17,53 +24,208 @@ impl BasicTokenStore {
     pub fn new() -> Self {
         Self {
             tokens: HashMap::new(),
+            symbols: HashMap::new(),
+            canonical: HashMap::new(),
         }
     }
+
+    /// Returns a snapshot of the tokens of the store.
+    ///
+    /// The canonical tokens chosen for shared symbols are not part of the
+    /// snapshot.
+    pub fn to_snapshot(&self) -> StoreSnapshot {
+        let mut snapshot = StoreSnapshot::default();
+
+        for ((chain_id, _), token) in &self.tokens {
+            snapshot
+                .tokens
+                .entry(*chain_id)
+                .or_default()
+                .push(token.clone());
+        }
+
+        for tokens in snapshot.tokens.values_mut() {
+            tokens.sort_by_key(|token| token.address);
+        }
+
+        snapshot
+    }
+
+    /// Retains only the canonical tokens chosen for the symbols matching the
+    /// given predicate.
+    #[cfg(feature = "redis-store")]
+    pub(super) fn retain_canonicals<F>(&mut self, mut f: F)
+    where
+        F: FnMut(u64, &str) -> bool,
+    {
+        self.canonical
+            .retain(|(chain_id, symbol), _| f(*chain_id, symbol));
+    }
+
+    /// Creates a store from the given snapshot.
+    pub fn from_snapshot(snapshot: StoreSnapshot) -> Self {
+        let mut store = Self::new();
+
+        for (chain_id, tokens) in snapshot.tokens {
+            for token in tokens {
+                store.insert(chain_id, token);
+            }
+        }
+
+        store
+    }
+}
+
+impl From<StoreSnapshot> for BasicTokenStore {
+    fn from(snapshot: StoreSnapshot) -> Self {
+        Self::from_snapshot(snapshot)
+    }
+}
+
+impl From<BasicTokenStore> for StoreSnapshot {
+    fn from(store: BasicTokenStore) -> Self {
+        store.to_snapshot()
+    }
 }
 
 impl<'a> TokenStore<'a> for BasicTokenStore {
     type Item = &'a Token;
 
     fn get(&'a self, chain_id: u64, id: TokenId) -> Option<Self::Item> {
-        self.tokens.get(&(chain_id, id.clone()))
+        let address = self.resolve(chain_id, id).ok()?;
+
+        self.tokens.get(&(chain_id, address))
     }
 
-    fn get_mut(&mut self, chain_id: u64, id: TokenId) -> Option<&mut Token> {
-        self.tokens.get_mut(&(chain_id, id.clone()))
+    fn get_mut(&mut self, chain_id: u64, id: TokenId) -> Option<TokenMut<'_>> {
+        let address = self.resolve(chain_id, id).ok()?;
+
+        self.tokens.get_mut(&(chain_id, address)).map(TokenMut::new)
     }
 
     fn insert(&mut self, chain_id: u64, token: Token) {
-        self.tokens
-            .insert((chain_id, TokenId::Address(token.address)), token.clone());
-        self.tokens
-            .insert((chain_id, TokenId::Symbol(token.symbol.to_string())), token);
+        let token = token.on_chain(chain_id);
+
+        let symbol = (chain_id, token.symbol.clone());
+
+        if let Some(previous) = self.tokens.insert((chain_id, token.address), token.clone()) {
+            if previous.symbol != token.symbol {
+                unindex(&mut self.symbols, chain_id, previous.symbol, token.address);
+            }
+        }
+
+        let addresses = self.symbols.entry(symbol).or_default();
+
+        if !addresses.contains(&token.address) {
+            addresses.push(token.address);
+        }
+    }
+
+    fn len(&'a self) -> usize {
+        self.tokens.len()
     }
 
     fn contains(&self, chain_id: u64, id: TokenId) -> bool {
-        self.tokens.contains_key(&(chain_id, id))
+        self.resolve(chain_id, id)
+            .is_ok_and(|address| self.tokens.contains_key(&(chain_id, address)))
+    }
+
+    fn symbol_addresses(&self, chain_id: u64, symbol: &str) -> Vec<Address> {
+        self.symbols
+            .get(&(chain_id, symbol.to_string()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn canonical(&self, chain_id: u64, symbol: &str) -> Option<Address> {
+        self.canonical.get(&(chain_id, symbol.to_string())).copied()
+    }
+
+    fn set_canonical(&mut self, chain_id: u64, symbol: String, address: Address) {
+        self.canonical.insert((chain_id, symbol), address);
     }
 
     fn symbols(&'a self, chain_id: Option<u64>) -> Vec<String> {
-        self.tokens
+        self.symbols
             .keys()
-            .filter_map(move |(token_chain_id, id)| match (id, chain_id) {
-                (TokenId::Symbol(id), Some(chain_id)) if token_chain_id == &chain_id => {
-                    Some(id.clone())
-                }
-                (TokenId::Symbol(id), None) => Some(id.clone()),
-                _ => None,
+            .filter(|(token_chain_id, _)| {
+                chain_id.is_none_or(|chain_id| chain_id == *token_chain_id)
             })
+            .map(|(_, symbol)| symbol.clone())
             .collect()
     }
 
     fn addresses(&'a self, chain_id: Option<u64>) -> Vec<Address> {
         self.tokens
             .keys()
-            .filter_map(move |(token_chain_id, id)| match (id, chain_id) {
-                (TokenId::Address(id), Some(chain_id)) if token_chain_id == &chain_id => Some(*id),
-                (TokenId::Address(id), None) => Some(*id),
-                _ => None,
+            .filter(|(token_chain_id, _)| {
+                chain_id.is_none_or(|chain_id| chain_id == *token_chain_id)
             })
+            .map(|(_, address)| *address)
             .collect()
     }
+
+    fn chain_ids(&'a self) -> Vec<u64> {
+        let mut chain_ids = self
+            .tokens
+            .keys()
+            .map(|(chain_id, _)| *chain_id)
+            .collect::<Vec<_>>();
+
+        chain_ids.sort_unstable();
+        chain_ids.dedup();
+
+        chain_ids
+    }
+}
+
+impl TokenStoreMut<'_> for BasicTokenStore {
+    fn remove(&mut self, chain_id: u64, id: TokenId) -> Option<Token> {
+        let address = self.resolve(chain_id, id).ok()?;
+        let token = self.tokens.remove(&(chain_id, address))?;
+
+        unindex(&mut self.symbols, chain_id, token.symbol.clone(), address);
+
+        Some(token)
+    }
+
+    fn clear(&mut self) {
+        self.tokens.clear();
+        self.symbols.clear();
+    }
+
+    fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(u64, &Token) -> bool,
+    {
+        let symbols = &mut self.symbols;
+
+        self.tokens.retain(|(chain_id, address), token| {
+            let keep = f(*chain_id, token);
+
+            if !keep {
+                unindex(symbols, *chain_id, token.symbol.clone(), *address);
+            }
+
+            keep
+        });
+    }
+}
+
+/// Removes the given address from the tokens with the given symbol.
+pub(super) fn unindex(
+    symbols: &mut HashMap<(u64, String), Vec<Address>>,
+    chain_id: u64,
+    symbol: String,
+    address: Address,
+) {
+    let key = (chain_id, symbol);
+
+    if let Some(addresses) = symbols.get_mut(&key) {
+        addresses.retain(|a| *a != address);
+
+        if addresses.is_empty() {
+            symbols.remove(&key);
+        }
+    }
 }
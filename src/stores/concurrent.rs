@@ -0,0 +1,235 @@
+use std::{future::Future, sync::Arc};
+
+use alloy::primitives::Address;
+use async_once_cell::OnceCell;
+use dashmap::DashMap;
+
+use crate::{error::InternalError, Error, Token, TokenId};
+
+/// A [`Token`] store that can be shared between tasks, as all its methods
+/// take `&self`.
+///
+/// Unlike a [`TokenStore`](crate::TokenStore) behind a lock, it's not
+/// borrowed while a missing token is fetched, and concurrent
+/// [`get_or_fetch`](Self::get_or_fetch) calls for the same token share a
+/// single fetch.
+///
+/// Like a [`TokenStore`](crate::TokenStore), a symbol shared by several
+/// tokens only identifies its canonical token, if one has been chosen with
+/// [`set_canonical`](Self::set_canonical).
+#[derive(Debug, Default)]
+pub struct ConcurrentTokenStore {
+    tokens: DashMap<(u64, TokenId), Arc<OnceCell<Token>>>,
+    symbols: DashMap<(u64, String), Vec<Address>>,
+    canonical: DashMap<(u64, String), Address>,
+}
+
+impl ConcurrentTokenStore {
+    /// Creates a new [`ConcurrentTokenStore`].
+    pub fn new() -> Self {
+        Self {
+            tokens: DashMap::new(),
+            symbols: DashMap::new(),
+            canonical: DashMap::new(),
+        }
+    }
+
+    /// Returns the token corresponding to the given id, or `None` if it's
+    /// missing or an ambiguous symbol.
+    pub fn get(&self, chain_id: u64, id: TokenId) -> Option<Token> {
+        let address = match id {
+            TokenId::Address(address) => address,
+            TokenId::Symbol(symbol) => self.resolve(chain_id, &symbol).ok()??,
+        };
+
+        self.tokens
+            .get(&(chain_id, TokenId::Address(address)))
+            .and_then(|cell| cell.get().cloned())
+    }
+
+    /// Inserts a token into the store, replacing the token with the same
+    /// address if any.
+    ///
+    /// See [`TokenStore::insert`](crate::TokenStore::insert) for the chain of
+    /// the token.
+    pub fn insert(&self, chain_id: u64, token: Token) {
+        let token = token.on_chain(chain_id);
+
+        let previous = self.tokens.insert(
+            (chain_id, TokenId::Address(token.address)),
+            Arc::new(OnceCell::new_with(token.clone())),
+        );
+
+        if let Some(previous) = previous.as_ref().and_then(|cell| cell.get()) {
+            if previous.symbol != token.symbol {
+                self.unindex(chain_id, &previous.symbol, token.address);
+            }
+        }
+
+        self.index(chain_id, &token);
+    }
+
+    /// Returns the addresses of the tokens with the given symbol, in their
+    /// insertion order.
+    pub fn symbol_addresses(&self, chain_id: u64, symbol: &str) -> Vec<Address> {
+        self.symbols
+            .get(&(chain_id, symbol.to_string()))
+            .map(|addresses| addresses.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the address of the canonical token with the given symbol, if
+    /// one has been chosen.
+    pub fn canonical(&self, chain_id: u64, symbol: &str) -> Option<Address> {
+        self.canonical
+            .get(&(chain_id, symbol.to_string()))
+            .map(|address| *address)
+    }
+
+    /// Chooses the token identified by the given symbol when it's shared by
+    /// several tokens.
+    pub fn set_canonical(&self, chain_id: u64, symbol: String, address: Address) {
+        self.canonical.insert((chain_id, symbol), address);
+    }
+
+    /// Returns true if the store contains the given token.
+    pub fn contains(&self, chain_id: u64, id: TokenId) -> bool {
+        self.get(chain_id, id).is_some()
+    }
+
+    /// Returns the addresses of the tokens of the given chain, or of all the
+    /// chains.
+    pub fn addresses(&self, chain_id: Option<u64>) -> Vec<Address> {
+        self.tokens
+            .iter()
+            .filter(|entry| entry.value().get().is_some())
+            .filter_map(|entry| match entry.key() {
+                (token_chain_id, TokenId::Address(address))
+                    if chain_id.is_none_or(|chain_id| chain_id == *token_chain_id) =>
+                {
+                    Some(*address)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the token corresponding to the given id if present, otherwise
+    /// fetches it using `fetch` and inserts it into the store.
+    ///
+    /// Concurrent calls for the same token wait for the first one to fetch
+    /// it. If the fetch fails, the next waiting call makes its own attempt,
+    /// and nothing is left in the store.
+    ///
+    /// Fails without fetching if the id is a symbol shared by several
+    /// tokens, none of them being canonical.
+    pub async fn get_or_fetch<F, Fut, E>(
+        &self,
+        chain_id: u64,
+        id: TokenId,
+        fetch: F,
+    ) -> Result<Token, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Token, E>>,
+        E: From<Error>,
+    {
+        // A known symbol is looked up from its token address, an unknown one
+        // is only used to share its fetch
+        let id = match id {
+            TokenId::Symbol(ref symbol) => match self.resolve(chain_id, symbol) {
+                Ok(None) => id,
+                Ok(Some(address)) => TokenId::Address(address),
+                Err(err) => return Err(Error::new(id, err).into()),
+            },
+            TokenId::Address(_) => id,
+        };
+
+        // The map shard is only locked while getting the cell, not while
+        // fetching the token.
+        let cell = self
+            .tokens
+            .entry((chain_id, id.clone()))
+            .or_default()
+            .clone();
+
+        if let Some(token) = cell.get() {
+            return Ok(token.clone());
+        }
+
+        let token = match cell
+            .get_or_try_init(async { Ok::<_, E>(fetch().await?.on_chain(chain_id)) })
+            .await
+        {
+            Ok(token) => token.clone(),
+            Err(err) => {
+                // The empty cell isn't kept, unless another call has replaced
+                // it in the meantime
+                self.tokens.remove_if(&(chain_id, id), |_, other| {
+                    Arc::ptr_eq(other, &cell) && other.get().is_none()
+                });
+
+                return Err(err);
+            }
+        };
+
+        // Makes the token available from its address and symbol, the symbol
+        // cell being only needed while fetching. The cell may also have been
+        // removed by a failed attempt before being filled by a waiting call.
+        if let TokenId::Symbol(_) = id {
+            self.tokens.remove(&(chain_id, id));
+        }
+
+        let mut cell = self
+            .tokens
+            .entry((chain_id, TokenId::Address(token.address)))
+            .or_default();
+
+        if cell.get().is_none() {
+            *cell = Arc::new(OnceCell::new_with(token.clone()));
+        }
+
+        drop(cell);
+
+        self.index(chain_id, &token);
+
+        Ok(token)
+    }
+
+    /// Returns the address of the token with the given symbol, if any,
+    /// failing if it's shared by several tokens, none of them being
+    /// canonical.
+    fn resolve(&self, chain_id: u64, symbol: &str) -> Result<Option<Address>, InternalError> {
+        let addresses = self.symbol_addresses(chain_id, symbol);
+
+        match addresses[..] {
+            [] => Ok(None),
+            [address] => Ok(Some(address)),
+            _ => match self.canonical(chain_id, symbol) {
+                Some(address) if addresses.contains(&address) => Ok(Some(address)),
+                _ => Err(InternalError::Ambiguous(symbol.to_string(), addresses)),
+            },
+        }
+    }
+
+    /// Makes the given token reachable from its symbol.
+    fn index(&self, chain_id: u64, token: &Token) {
+        let mut addresses = self
+            .symbols
+            .entry((chain_id, token.symbol.clone()))
+            .or_default();
+
+        if !addresses.contains(&token.address) {
+            addresses.push(token.address);
+        }
+    }
+
+    /// Removes the given address from the tokens with the given symbol.
+    fn unindex(&self, chain_id: u64, symbol: &str, address: Address) {
+        self.symbols
+            .remove_if_mut(&(chain_id, symbol.to_string()), |_, addresses| {
+                addresses.retain(|a| *a != address);
+                addresses.is_empty()
+            });
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{Token, TokenId};
 
-use super::TokenStore;
+use super::{TokenMut, TokenStore, TokenStoreMut};
 
 /// A view into a single entry in a map, which may either be vacant or occupied.
 #[derive(Debug)]
@@ -9,7 +9,7 @@ where
     S: TokenStore<'a>,
 {
     /// An occupied entry.
-    Occupied(OccupiedEntry<'a>),
+    Occupied(OccupiedEntry<'a, S>),
 
     /// A vacant entry.
     Vacant(VacantEntry<'a, S>),
@@ -21,43 +21,74 @@ where
 {
     /// Creates a new [`Entry`].
     pub fn new(chain_id: u64, id: TokenId, store: &'a mut S) -> Self {
-        if store.contains(chain_id, id.clone()) {
-            Self::Occupied(OccupiedEntry::new(chain_id, id, store))
-        } else {
-            Self::Vacant(VacantEntry::new(chain_id, id, store))
+        let token = store
+            .get_mut(chain_id, id.clone())
+            .map(|token| token.clone());
+
+        match token {
+            Some(token) => Self::Occupied(OccupiedEntry::new(chain_id, token, store)),
+            None => Self::Vacant(VacantEntry::new(chain_id, id, store)),
         }
     }
 }
 
 #[derive(Debug)]
-pub struct OccupiedEntry<'a> {
-    value: &'a mut Token,
+pub struct OccupiedEntry<'a, S>
+where
+    S: TokenStore<'a>,
+{
+    chain_id: u64,
+    // A copy of the token, kept up to date through the entry
+    token: Token,
+    store: &'a mut S,
 }
 
-impl<'a> OccupiedEntry<'a> {
-    pub fn new<S>(chain_id: u64, id: TokenId, store: &'a mut S) -> Self
-    where
-        S: TokenStore<'a>,
-    {
+impl<'a, S> OccupiedEntry<'a, S>
+where
+    S: TokenStore<'a>,
+{
+    pub const fn new(chain_id: u64, token: Token, store: &'a mut S) -> Self {
         Self {
-            value: store.get_mut(chain_id, id).unwrap(),
+            chain_id,
+            token,
+            store,
         }
     }
 
     /// Gets a reference to the value in the entry.
-    pub fn get(&self) -> &Token {
-        self.value
+    pub const fn get(&self) -> &Token {
+        &self.token
     }
 
     /// Gets a mutable reference to the value in the entry.
-    pub fn get_mut(&mut self) -> &mut Token {
-        self.value
+    pub fn get_mut(&mut self) -> TokenMut<'_> {
+        // The token is reached from its address, as it's always unambiguous
+        let token = self
+            .store
+            .entry_mut(self.chain_id, self.token.address)
+            .unwrap();
+
+        TokenMut::mirrored(token, &mut self.token)
     }
 
     /// Converts the `OccupiedEntry` into a mutable reference to the value in the entry
     /// with a lifetime bound to the map itself.
-    pub fn into_mut(self) -> &'a mut Token {
-        self.value
+    pub fn into_mut(self) -> TokenMut<'a> {
+        self.store
+            .entry_mut(self.chain_id, self.token.address)
+            .unwrap()
+    }
+}
+
+impl<'a, S> OccupiedEntry<'a, S>
+where
+    S: TokenStoreMut<'a>,
+{
+    /// Takes the value out of the entry, and returns it.
+    pub fn remove(self) -> Token {
+        self.store
+            .remove(self.chain_id, TokenId::Address(self.token.address))
+            .unwrap()
     }
 }
 
@@ -75,7 +106,7 @@ impl<'a, S> VacantEntry<'a, S>
 where
     S: TokenStore<'a>,
 {
-    pub fn new(chain_id: u64, id: TokenId, store: &'a mut S) -> Self {
+    pub const fn new(chain_id: u64, id: TokenId, store: &'a mut S) -> Self {
         Self {
             chain_id,
             id,
@@ -95,8 +126,12 @@ where
         self.chain_id
     }
 
-    pub fn insert(self, token: Token) -> &'a mut Token {
+    pub fn insert(self, token: Token) -> TokenMut<'a> {
+        let address = token.address;
+
+        // The token is got back from its address, as its symbol may be
+        // shared by other tokens
         self.store.insert(self.chain_id, token);
-        self.store.get_mut(self.chain_id, self.id.clone()).unwrap()
+        self.store.entry_mut(self.chain_id, address).unwrap()
     }
 }
@@ -0,0 +1,454 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Deref,
+};
+
+use alloy::primitives::Address;
+
+use crate::{Token, TokenId};
+
+use super::{TokenMut, TokenStore, TokenStoreMut};
+
+/// The layers of a [`LayeredTokenStore`] that tokens are written to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WritePolicy {
+    /// Tokens are only written to the upper layer.
+    Upper,
+    /// Tokens are only written to the lower layer, the upper one being
+    /// read-only.
+    Lower,
+    /// Tokens are written to both layers.
+    #[default]
+    Both,
+}
+
+impl WritePolicy {
+    const fn upper(self) -> bool {
+        matches!(self, Self::Upper | Self::Both)
+    }
+
+    const fn lower(self) -> bool {
+        matches!(self, Self::Lower | Self::Both)
+    }
+}
+
+/// A [`TokenStore`] composed of two layers, like a hot in-memory store over
+/// a persistent one.
+///
+/// The upper layer is consulted first. Tokens are written to the layers
+/// chosen by the [`WritePolicy`], and the tokens found in the lower layer
+/// are promoted to the upper one when it's written to, on mutable accesses
+/// like [`Erc20ProviderExt::get_token`](crate::Erc20ProviderExt::get_token).
+/// More layers can be stacked by using a [`LayeredTokenStore`] as the lower
+/// layer.
+///
+/// The layer that isn't written to, if any, is never changed: its tokens
+/// are copied to the other layer to be mutated, and the removed ones are
+/// masked instead.
+#[derive(Debug)]
+pub struct LayeredTokenStore<U, L> {
+    upper: U,
+    lower: L,
+    policy: WritePolicy,
+    canonical: HashMap<(u64, String), Address>,
+    mask: Mask,
+}
+
+/// The tokens of the read-only layer of a [`LayeredTokenStore`] hidden by
+/// removals or by their copy in the other layer.
+#[derive(Debug, Default)]
+struct Mask {
+    all: bool,
+    tokens: HashSet<(u64, Address)>,
+}
+
+impl Mask {
+    fn hides(&self, chain_id: u64, address: Address) -> bool {
+        self.all || self.tokens.contains(&(chain_id, address))
+    }
+}
+
+impl<U, L> LayeredTokenStore<U, L> {
+    /// Creates a new [`LayeredTokenStore`], writing tokens to both layers.
+    pub fn new(upper: U, lower: L) -> Self {
+        Self::with_policy(upper, lower, WritePolicy::Both)
+    }
+
+    /// Creates a new [`LayeredTokenStore`], writing tokens to the layers
+    /// chosen by the given policy.
+    pub fn with_policy(upper: U, lower: L, policy: WritePolicy) -> Self {
+        Self {
+            upper,
+            lower,
+            policy,
+            canonical: HashMap::new(),
+            mask: Mask::default(),
+        }
+    }
+
+    /// Returns the upper layer.
+    pub const fn upper(&self) -> &U {
+        &self.upper
+    }
+
+    /// Returns the lower layer.
+    pub const fn lower(&self) -> &L {
+        &self.lower
+    }
+
+    /// Returns the layers.
+    ///
+    /// The tokens masked in the read-only layer are present again in it.
+    pub fn into_inner(self) -> (U, L) {
+        (self.upper, self.lower)
+    }
+
+    /// Returns whether the given token of the upper layer is visible.
+    fn upper_visible(&self, chain_id: u64, address: Address) -> bool {
+        self.policy.upper() || !self.mask.hides(chain_id, address)
+    }
+
+    /// Returns whether the given token of the lower layer is visible.
+    fn lower_visible(&self, chain_id: u64, address: Address) -> bool {
+        self.policy.lower() || !self.mask.hides(chain_id, address)
+    }
+}
+
+/// A reference to a token of a [`LayeredTokenStore`] layer.
+#[derive(Debug)]
+pub enum LayerItem<U, L> {
+    /// A token of the upper layer.
+    Upper(U),
+    /// A token of the lower layer.
+    Lower(L),
+}
+
+impl<U, L> Deref for LayerItem<U, L>
+where
+    U: Deref<Target = Token>,
+    L: Deref<Target = Token>,
+{
+    type Target = Token;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Upper(token) => token,
+            Self::Lower(token) => token,
+        }
+    }
+}
+
+impl<'a, U, L> LayeredTokenStore<U, L>
+where
+    U: TokenStore<'a>,
+    L: TokenStore<'a>,
+{
+    /// Returns whether the upper layer has the given visible token.
+    fn upper_contains(&self, chain_id: u64, address: Address) -> bool {
+        self.upper_visible(chain_id, address)
+            && self.upper.contains(chain_id, TokenId::Address(address))
+    }
+
+    /// Returns whether the lower layer has the given visible token.
+    fn lower_contains(&self, chain_id: u64, address: Address) -> bool {
+        self.lower_visible(chain_id, address)
+            && self.lower.contains(chain_id, TokenId::Address(address))
+    }
+
+    /// Masks the given token of the read-only layer, if any.
+    fn hide(&mut self, chain_id: u64, address: Address) {
+        let present = match self.policy {
+            WritePolicy::Upper => self.lower.contains(chain_id, TokenId::Address(address)),
+            WritePolicy::Lower => self.upper.contains(chain_id, TokenId::Address(address)),
+            WritePolicy::Both => false,
+        };
+
+        if present && !self.mask.all {
+            self.mask.tokens.insert((chain_id, address));
+        }
+    }
+}
+
+impl<'a, U, L> TokenStore<'a> for LayeredTokenStore<U, L>
+where
+    U: TokenStore<'a>,
+    L: TokenStore<'a>,
+{
+    type Item = LayerItem<U::Item, L::Item>;
+
+    fn get(&'a self, chain_id: u64, id: TokenId) -> Option<Self::Item> {
+        let address = self.resolve(chain_id, id).ok()?;
+
+        if self.upper_visible(chain_id, address) {
+            if let Some(token) = self.upper.get(chain_id, TokenId::Address(address)) {
+                return Some(LayerItem::Upper(token));
+            }
+        }
+
+        if !self.lower_visible(chain_id, address) {
+            return None;
+        }
+
+        self.lower
+            .get(chain_id, TokenId::Address(address))
+            .map(LayerItem::Lower)
+    }
+
+    fn get_mut(&mut self, chain_id: u64, id: TokenId) -> Option<TokenMut<'_>> {
+        let address = self.resolve(chain_id, id).ok()?;
+        let id = TokenId::Address(address);
+
+        if self.policy == WritePolicy::Lower {
+            // The read-only upper copy is copied to the lower layer, and
+            // masked by it
+            if !self.lower.contains(chain_id, id.clone()) {
+                if !self.upper_contains(chain_id, address) {
+                    return None;
+                }
+
+                let token = self.upper.get_mut(chain_id, id.clone())?.clone();
+
+                self.lower.insert(chain_id, token);
+            }
+
+            self.hide(chain_id, address);
+
+            return self.lower.get_mut(chain_id, id);
+        }
+
+        if !self.upper.contains(chain_id, id.clone()) {
+            if !self.lower_contains(chain_id, address) {
+                return None;
+            }
+
+            let token = self.lower.get_mut(chain_id, id.clone())?.clone();
+
+            self.upper.insert(chain_id, token);
+        }
+
+        self.upper.get_mut(chain_id, id)
+    }
+
+    fn entry_mut(&mut self, chain_id: u64, address: Address) -> Option<TokenMut<'_>> {
+        // The token of an entry has been inserted or copied into the layer
+        // written to
+        if self.policy == WritePolicy::Lower {
+            self.lower.entry_mut(chain_id, address)
+        } else {
+            self.upper.entry_mut(chain_id, address)
+        }
+    }
+
+    fn insert(&mut self, chain_id: u64, token: Token) {
+        if !self.policy.upper() {
+            self.hide(chain_id, token.address);
+        }
+
+        if self.policy.lower() {
+            self.lower.insert(chain_id, token.clone());
+        }
+
+        if self.policy.upper() {
+            self.upper.insert(chain_id, token);
+        }
+    }
+
+    /// Returns the number of visible tokens, the tokens present in both
+    /// layers being counted once.
+    fn len(&'a self) -> usize {
+        self.addresses(None).len()
+    }
+
+    fn contains(&self, chain_id: u64, id: TokenId) -> bool {
+        self.resolve(chain_id, id).is_ok_and(|address| {
+            self.upper_contains(chain_id, address) || self.lower_contains(chain_id, address)
+        })
+    }
+
+    fn symbol_addresses(&self, chain_id: u64, symbol: &str) -> Vec<Address> {
+        let mut addresses = self
+            .upper
+            .symbol_addresses(chain_id, symbol)
+            .into_iter()
+            .filter(|address| self.upper_visible(chain_id, *address))
+            .collect::<Vec<_>>();
+
+        for address in self.lower.symbol_addresses(chain_id, symbol) {
+            if self.lower_visible(chain_id, address) && !addresses.contains(&address) {
+                addresses.push(address);
+            }
+        }
+
+        addresses
+    }
+
+    fn canonical(&self, chain_id: u64, symbol: &str) -> Option<Address> {
+        self.canonical
+            .get(&(chain_id, symbol.to_string()))
+            .copied()
+            .or_else(|| self.upper.canonical(chain_id, symbol))
+            .or_else(|| self.lower.canonical(chain_id, symbol))
+    }
+
+    fn set_canonical(&mut self, chain_id: u64, symbol: String, address: Address) {
+        self.canonical.insert((chain_id, symbol), address);
+    }
+
+    /// Returns the symbols of the visible tokens.
+    fn symbols(&'a self, chain_id: Option<u64>) -> Vec<String> {
+        let Some(chain_id) = chain_id else {
+            return self
+                .chain_ids()
+                .into_iter()
+                .flat_map(|chain_id| self.symbols(Some(chain_id)))
+                .collect();
+        };
+
+        let mut symbols = Vec::new();
+
+        for symbol in self
+            .upper
+            .symbols(Some(chain_id))
+            .into_iter()
+            .chain(self.lower.symbols(Some(chain_id)))
+        {
+            if !symbols.contains(&symbol) && !self.symbol_addresses(chain_id, &symbol).is_empty() {
+                symbols.push(symbol);
+            }
+        }
+
+        symbols
+    }
+
+    /// Returns the addresses of the visible tokens.
+    fn addresses(&'a self, chain_id: Option<u64>) -> Vec<Address> {
+        let Some(chain_id) = chain_id else {
+            return self
+                .chain_ids()
+                .into_iter()
+                .flat_map(|chain_id| self.addresses(Some(chain_id)))
+                .collect();
+        };
+
+        let mut addresses = self
+            .upper
+            .addresses(Some(chain_id))
+            .into_iter()
+            .filter(|address| self.upper_visible(chain_id, *address))
+            .collect::<Vec<_>>();
+
+        for address in self.lower.addresses(Some(chain_id)) {
+            if self.lower_visible(chain_id, address) && !addresses.contains(&address) {
+                addresses.push(address);
+            }
+        }
+
+        addresses
+    }
+
+    fn chain_ids(&'a self) -> Vec<u64> {
+        let mut chain_ids = self.upper.chain_ids();
+
+        chain_ids.extend(self.lower.chain_ids());
+        chain_ids.sort_unstable();
+        chain_ids.dedup();
+        chain_ids.retain(|chain_id| !self.addresses(Some(*chain_id)).is_empty());
+
+        chain_ids
+    }
+}
+
+impl<'a, U, L> TokenStoreMut<'a> for LayeredTokenStore<U, L>
+where
+    U: TokenStoreMut<'a>,
+    L: TokenStoreMut<'a>,
+{
+    /// Removes the given token from the layers written to, masking it in
+    /// the other one.
+    fn remove(&mut self, chain_id: u64, id: TokenId) -> Option<Token> {
+        let address = self.resolve(chain_id, id).ok()?;
+        let id = TokenId::Address(address);
+
+        // The copy of the read-only layer is returned when it's the only one
+        let masked = match self.policy {
+            WritePolicy::Upper if self.lower_contains(chain_id, address) => self
+                .lower
+                .get_mut(chain_id, id.clone())
+                .map(|token| token.clone()),
+            WritePolicy::Lower if self.upper_contains(chain_id, address) => self
+                .upper
+                .get_mut(chain_id, id.clone())
+                .map(|token| token.clone()),
+            _ => None,
+        };
+
+        self.hide(chain_id, address);
+
+        let upper = self
+            .policy
+            .upper()
+            .then(|| self.upper.remove(chain_id, id.clone()))
+            .flatten();
+
+        let lower = self
+            .policy
+            .lower()
+            .then(|| self.lower.remove(chain_id, id))
+            .flatten();
+
+        upper.or(lower).or(masked)
+    }
+
+    /// Clears the layers written to, masking the other one.
+    fn clear(&mut self) {
+        if self.policy.upper() {
+            self.upper.clear();
+        }
+
+        if self.policy.lower() {
+            self.lower.clear();
+        }
+
+        if self.policy != WritePolicy::Both {
+            self.mask.all = true;
+            self.mask.tokens.clear();
+        }
+    }
+
+    /// Retains the tokens of the layers written to, masking the tokens of
+    /// the other one instead of removing them.
+    fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(u64, &Token) -> bool,
+    {
+        let mut masked = Vec::new();
+
+        match self.policy {
+            WritePolicy::Upper if !self.mask.all => self.lower.retain(|chain_id, token| {
+                if !f(chain_id, token) {
+                    masked.push((chain_id, token.address));
+                }
+
+                true
+            }),
+            WritePolicy::Lower if !self.mask.all => self.upper.retain(|chain_id, token| {
+                if !f(chain_id, token) {
+                    masked.push((chain_id, token.address));
+                }
+
+                true
+            }),
+            _ => {}
+        }
+
+        self.mask.tokens.extend(masked);
+
+        if self.policy.upper() {
+            self.upper.retain(&mut f);
+        }
+
+        if self.policy.lower() {
+            self.lower.retain(&mut f);
+        }
+    }
+}
@@ -1,80 +1,170 @@
-use std::num::NonZeroUsize;
+use std::{collections::HashMap, num::NonZeroUsize};
 
 use alloy::primitives::Address;
 use lru::LruCache;
-use parking_lot::{MappedRwLockWriteGuard, RwLock, RwLockWriteGuard};
+use parking_lot::RwLock;
 
 use crate::{Token, TokenId};
 
-use super::TokenStore;
+use super::{basic::unindex, TokenMut, TokenStore, TokenStoreMut};
 
 /// A basic [`TokenStore`] implementation.
 #[derive(Debug)]
 pub struct LruTokenStore {
-    tokens: RwLock<LruCache<(u64, TokenId), Token>>,
+    tokens: HashMap<(u64, Address), Token>,
+    recency: RwLock<LruCache<(u64, Address), ()>>,
+    symbols: HashMap<(u64, String), Vec<Address>>,
+    canonical: HashMap<(u64, String), Address>,
 }
 
 impl LruTokenStore {
     /// Creates a new [`LruTokenStore`]
     pub fn new(cap: NonZeroUsize) -> Self {
         Self {
-            tokens: RwLock::new(LruCache::new(cap)),
+            tokens: HashMap::new(),
+            recency: RwLock::new(LruCache::new(cap)),
+            symbols: HashMap::new(),
+            canonical: HashMap::new(),
         }
     }
 }
 
 impl<'a> TokenStore<'a> for LruTokenStore {
-    type Item = MappedRwLockWriteGuard<'a, Token>;
+    type Item = &'a Token;
 
     fn get(&'a self, chain_id: u64, id: TokenId) -> Option<Self::Item> {
-        RwLockWriteGuard::try_map(self.tokens.write(), |tokens| {
-            tokens.get_mut(&(chain_id, id))
-        })
-        .ok()
+        let address = self.resolve(chain_id, id).ok()?;
+
+        self.recency.write().get(&(chain_id, address))?;
+        self.tokens.get(&(chain_id, address))
     }
 
-    fn get_mut(&mut self, chain_id: u64, id: TokenId) -> Option<&mut Token> {
-        self.tokens.get_mut().get_mut(&(chain_id, id))
+    fn get_mut(&mut self, chain_id: u64, id: TokenId) -> Option<TokenMut<'_>> {
+        let address = self.resolve(chain_id, id).ok()?;
+
+        self.recency.get_mut().get(&(chain_id, address))?;
+        self.tokens.get_mut(&(chain_id, address)).map(TokenMut::new)
     }
 
     fn insert(&mut self, chain_id: u64, token: Token) {
-        let mut tokens = self.tokens.write();
+        let token = token.on_chain(chain_id);
+
+        let key = (chain_id, token.address);
+        let symbol = (chain_id, token.symbol.clone());
+
+        // Evicts the least recently used token if the store is full
+        if let Some((evicted, _)) = self.recency.get_mut().push(key, ()) {
+            if evicted != key {
+                if let Some(token) = self.tokens.remove(&evicted) {
+                    unindex(&mut self.symbols, evicted.0, token.symbol, evicted.1);
+                }
+            }
+        }
+
+        if let Some(previous) = self.tokens.insert(key, token.clone()) {
+            if previous.symbol != token.symbol {
+                unindex(&mut self.symbols, chain_id, previous.symbol, token.address);
+            }
+        }
+
+        let addresses = self.symbols.entry(symbol).or_default();
 
-        tokens.put((chain_id, TokenId::Address(token.address)), token.clone());
-        tokens.put((chain_id, TokenId::Symbol(token.symbol.to_string())), token);
+        if !addresses.contains(&token.address) {
+            addresses.push(token.address);
+        }
+    }
+
+    fn len(&'a self) -> usize {
+        self.tokens.len()
     }
 
     fn contains(&self, chain_id: u64, id: TokenId) -> bool {
-        let tokens = self.tokens.read();
+        self.resolve(chain_id, id)
+            .is_ok_and(|address| self.tokens.contains_key(&(chain_id, address)))
+    }
 
-        tokens.contains(&(chain_id, id))
+    fn symbol_addresses(&self, chain_id: u64, symbol: &str) -> Vec<Address> {
+        self.symbols
+            .get(&(chain_id, symbol.to_string()))
+            .cloned()
+            .unwrap_or_default()
     }
 
-    fn symbols(&'a self, chain_id: Option<u64>) -> Vec<String> {
-        let tokens = self.tokens.read();
+    fn canonical(&self, chain_id: u64, symbol: &str) -> Option<Address> {
+        self.canonical.get(&(chain_id, symbol.to_string())).copied()
+    }
 
-        tokens
-            .iter()
-            .filter_map(move |((token_chain_id, id), _)| match (id, chain_id) {
-                (TokenId::Symbol(id), Some(chain_id)) if token_chain_id == &chain_id => {
-                    Some(id.clone())
-                }
-                (TokenId::Symbol(id), None) => Some(id.clone()),
-                _ => None,
+    fn set_canonical(&mut self, chain_id: u64, symbol: String, address: Address) {
+        self.canonical.insert((chain_id, symbol), address);
+    }
+
+    fn symbols(&'a self, chain_id: Option<u64>) -> Vec<String> {
+        self.symbols
+            .keys()
+            .filter(|(token_chain_id, _)| {
+                chain_id.is_none_or(|chain_id| chain_id == *token_chain_id)
             })
-            .collect::<Vec<_>>()
+            .map(|(_, symbol)| symbol.clone())
+            .collect()
     }
 
     fn addresses(&'a self, chain_id: Option<u64>) -> Vec<Address> {
-        let tokens = self.tokens.read();
-
-        tokens
-            .iter()
-            .filter_map(move |((token_chain_id, id), _)| match (id, chain_id) {
-                (TokenId::Address(id), Some(chain_id)) if token_chain_id == &chain_id => Some(*id),
-                (TokenId::Address(id), None) => Some(*id),
-                _ => None,
+        self.tokens
+            .keys()
+            .filter(|(token_chain_id, _)| {
+                chain_id.is_none_or(|chain_id| chain_id == *token_chain_id)
             })
-            .collect::<Vec<_>>()
+            .map(|(_, address)| *address)
+            .collect()
+    }
+
+    fn chain_ids(&'a self) -> Vec<u64> {
+        let mut chain_ids = self
+            .tokens
+            .keys()
+            .map(|(chain_id, _)| *chain_id)
+            .collect::<Vec<_>>();
+
+        chain_ids.sort_unstable();
+        chain_ids.dedup();
+
+        chain_ids
+    }
+}
+
+impl TokenStoreMut<'_> for LruTokenStore {
+    fn remove(&mut self, chain_id: u64, id: TokenId) -> Option<Token> {
+        let address = self.resolve(chain_id, id).ok()?;
+        let token = self.tokens.remove(&(chain_id, address))?;
+
+        self.recency.get_mut().pop(&(chain_id, address));
+        unindex(&mut self.symbols, chain_id, token.symbol.clone(), address);
+
+        Some(token)
+    }
+
+    fn clear(&mut self) {
+        self.tokens.clear();
+        self.recency.get_mut().clear();
+        self.symbols.clear();
+    }
+
+    fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(u64, &Token) -> bool,
+    {
+        let recency = self.recency.get_mut();
+        let symbols = &mut self.symbols;
+
+        self.tokens.retain(|(chain_id, address), token| {
+            let keep = f(*chain_id, token);
+
+            if !keep {
+                recency.pop(&(*chain_id, *address));
+                unindex(symbols, *chain_id, token.symbol.clone(), *address);
+            }
+
+            keep
+        });
     }
 }
@@ -1,16 +1,62 @@
 mod basic;
 pub use basic::BasicTokenStore;
 
+#[cfg(feature = "concurrent-store")]
+mod concurrent;
+#[cfg(feature = "concurrent-store")]
+pub use concurrent::ConcurrentTokenStore;
+
 mod entry;
 pub use entry::Entry;
 
+mod layered;
+pub use layered::{LayerItem, LayeredTokenStore, WritePolicy};
+
 #[cfg(feature = "lru-store")]
 mod lru;
 #[cfg(feature = "lru-store")]
 pub use lru::LruTokenStore;
 
+#[cfg(feature = "redb-store")]
+mod redb;
+#[cfg(feature = "redb-store")]
+pub use redb::RedbTokenStore;
+
+#[cfg(feature = "redis-store")]
+mod redis;
+#[cfg(feature = "redis-store")]
+pub use redis::RedisTokenStore;
+
+mod snapshot;
+pub use snapshot::StoreSnapshot;
+
 mod store_iter;
 pub use store_iter::StoreIter;
 
+mod token_mut;
+pub use token_mut::TokenMut;
+
 mod token_store;
-pub use token_store::TokenStore;
+pub use token_store::{TokenStore, TokenStoreMut};
+
+mod ttl;
+pub use ttl::{Clock, SystemClock, TtlTokenStore};
+
+#[cfg(any(feature = "redb-store", feature = "redis-store"))]
+mod write_back;
+
+/// Removes an occurrence of each of the given items from the list, like the
+/// tokens listed once per chain by [`TokenStore::addresses`].
+fn remove_each<T, I>(mut items: Vec<T>, removed: I) -> Vec<T>
+where
+    T: PartialEq,
+    I: IntoIterator<Item = T>,
+{
+    for item in removed {
+        if let Some(index) = items.iter().position(|other| *other == item) {
+            items.remove(index);
+        }
+    }
+
+    items
+}
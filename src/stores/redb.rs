@@ -0,0 +1,109 @@
+use std::path::Path;
+
+use alloy::primitives::Address;
+use redb::{Database, ReadableTable, TableDefinition};
+
+use crate::Token;
+
+use super::write_back::{forward_to_write_back, WriteBack};
+
+/// The tokens table, indexed by chain id and address, holding the tokens
+/// serialized as JSON.
+const TOKENS: TableDefinition<'_, (u64, &[u8; 20]), &str> = TableDefinition::new("tokens");
+
+/// The canonical tokens table, indexed by chain id and symbol.
+const CANONICALS: TableDefinition<'_, (u64, &str), &[u8; 20]> = TableDefinition::new("canonicals");
+
+/// A persistent [`TokenStore`](crate::TokenStore) implementation, backed by
+/// a [`redb`] database.
+///
+/// The tokens are persisted along with their metadata, and the canonical
+/// tokens chosen for shared symbols.
+///
+/// All the tokens are kept in memory, and inserted, mutated or removed
+/// tokens are only written to the database on [`flush`](Self::flush), which
+/// must be called to persist them: the changes not flushed when the store is
+/// dropped are lost.
+#[derive(Debug)]
+pub struct RedbTokenStore {
+    db: Database,
+    tokens: WriteBack,
+}
+
+impl RedbTokenStore {
+    /// Opens the [`RedbTokenStore`] at the given path, creating it if it
+    /// doesn't exist, and loads its tokens.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, redb::Error> {
+        let db = Database::create(path)?;
+
+        let tx = db.begin_write()?;
+        tx.open_table(TOKENS)?;
+        tx.open_table(CANONICALS)?;
+        tx.commit()?;
+
+        let mut tokens = WriteBack::new();
+        let tx = db.begin_read()?;
+
+        for entry in tx.open_table(TOKENS)?.iter()? {
+            let (_, value) = entry?;
+            let token = serde_json::from_str::<Token>(value.value())
+                .map_err(|err| redb::StorageError::Corrupted(err.to_string()))?;
+
+            tokens.load(token);
+        }
+
+        for entry in tx.open_table(CANONICALS)?.iter()? {
+            let (key, value) = entry?;
+            let (chain_id, symbol) = key.value();
+
+            tokens.load_canonical(chain_id, symbol.to_string(), Address::from(value.value()));
+        }
+
+        Ok(Self { db, tokens })
+    }
+
+    /// Writes the tokens inserted, mutated or removed, and the canonical
+    /// tokens chosen, since the last flush to the database.
+    ///
+    /// On failure, the changes are kept to be written by the next flush.
+    pub fn flush(&mut self) -> Result<(), redb::Error> {
+        if self.tokens.is_clean() {
+            return Ok(());
+        }
+
+        let tx = self.db.begin_write()?;
+
+        {
+            let mut table = tx.open_table(TOKENS)?;
+
+            for (chain_id, address, token) in self.tokens.changed_tokens() {
+                let key = (chain_id, &address.into_array());
+
+                match token {
+                    Some(token) => {
+                        let value = serde_json::to_string(token)
+                            .map_err(|err| redb::StorageError::Corrupted(err.to_string()))?;
+
+                        table.insert(key, value.as_str())?;
+                    }
+                    None => {
+                        table.remove(key)?;
+                    }
+                }
+            }
+
+            let mut table = tx.open_table(CANONICALS)?;
+
+            for (chain_id, symbol, address) in self.tokens.changed_canonicals() {
+                table.insert((chain_id, symbol), &address.into_array())?;
+            }
+        }
+
+        tx.commit()?;
+        self.tokens.mark_written();
+
+        Ok(())
+    }
+}
+
+forward_to_write_back!(RedbTokenStore, tokens);
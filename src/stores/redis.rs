@@ -0,0 +1,272 @@
+use std::fmt::{self, Debug};
+
+use alloy::{network::Network, primitives::Address};
+use redis::{aio::MultiplexedConnection, AsyncCommands, IntoConnectionInfo, RedisResult};
+
+use crate::{provider::retrieve_chain_token, Erc20ProviderExt, Error, Token, TokenId, TokenStore};
+
+use super::write_back::{forward_to_write_back, WriteBack};
+
+/// A [`TokenStore`] implementation shared through a Redis server, or any
+/// server speaking its protocol.
+///
+/// The tokens of each chain are stored in a hash at `<prefix>:<chain_id>`,
+/// mapping the checksummed token addresses to the tokens serialized as JSON,
+/// along with their metadata. The canonical tokens chosen for shared symbols
+/// are stored in a hash at `<prefix>:<chain_id>:canonical`, mapping the
+/// symbols to the checksummed token addresses.
+///
+/// The store is a replica of the shared tokens, all kept in memory: the
+/// [`TokenStore`] methods never reach the server, so a token missing from
+/// memory is retrieved from its contract by
+/// [`Erc20ProviderExt::get_token`], even when shared by another process, and
+/// is only shared on the next [`flush`](Self::flush). Use
+/// [`get_or_retrieve`](Self::get_or_retrieve) instead to read through to the
+/// server first, and share the retrieved tokens with the fleet.
+///
+/// Otherwise, the replica must be synchronized explicitly: tokens inserted,
+/// mutated or removed are only written to the server on
+/// [`flush`](Self::flush), which must be called to share them, the changes
+/// not flushed when the store is dropped being lost. The tokens written by
+/// other processes are loaded on [`pull`](Self::pull), or one by one with
+/// [`load`](Self::load).
+///
+/// The server is reached through an async connection, requiring a Tokio
+/// runtime.
+pub struct RedisTokenStore {
+    conn: MultiplexedConnection,
+    prefix: String,
+    tokens: WriteBack,
+}
+
+impl RedisTokenStore {
+    /// Connects to the given server, and loads the tokens stored under the
+    /// given key prefix.
+    pub async fn open(
+        info: impl IntoConnectionInfo,
+        prefix: impl Into<String>,
+    ) -> RedisResult<Self> {
+        let conn = redis::Client::open(info)?
+            .get_multiplexed_async_connection()
+            .await?;
+
+        let mut store = Self {
+            conn,
+            prefix: prefix.into(),
+            tokens: WriteBack::new(),
+        };
+
+        store.pull().await?;
+
+        Ok(store)
+    }
+
+    /// Loads the tokens stored on the server, replacing the ones held in
+    /// memory unless they have not been flushed yet.
+    ///
+    /// The keys are listed with `SCAN`, not blocking the server.
+    pub async fn pull(&mut self) -> RedisResult<()> {
+        let pattern = format!("{}:*", escape_pattern(&self.prefix));
+        let mut keys = Vec::new();
+        let mut cursor = 0;
+
+        loop {
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .query_async(&mut self.conn)
+                .await?;
+
+            keys.extend(batch);
+
+            if next == 0 {
+                break;
+            }
+
+            cursor = next;
+        }
+
+        let mut tokens = Vec::new();
+        let mut canonicals = Vec::new();
+
+        for key in keys {
+            let Some((chain_id, canonical)) = self.parse_key(&key) else {
+                continue;
+            };
+
+            let fields: Vec<(String, String)> = self.conn.hgetall(&key).await?;
+
+            for (field, value) in fields {
+                if canonical {
+                    if let Ok(address) = value.parse() {
+                        canonicals.push((chain_id, field, address));
+                    }
+                } else if let Some(token) = decode(chain_id, &value) {
+                    tokens.push(token);
+                }
+            }
+        }
+
+        self.tokens.replace(tokens, canonicals);
+
+        Ok(())
+    }
+
+    /// Returns the token at the given address, loading it from the server
+    /// if it's missing from memory.
+    ///
+    /// The tokens removed but not flushed yet are not loaded again.
+    pub async fn load(&mut self, chain_id: u64, address: Address) -> RedisResult<Option<&Token>> {
+        let id = TokenId::Address(address);
+
+        if !self.tokens.contains(chain_id, id.clone()) && !self.tokens.is_dirty(chain_id, address) {
+            let value: Option<String> = self
+                .conn
+                .hget(self.key(chain_id), address.to_checksum(None))
+                .await?;
+
+            if let Some(token) = value.and_then(|value| decode(chain_id, &value)) {
+                self.tokens.load(token);
+            }
+        }
+
+        Ok(self.tokens.get(chain_id, id))
+    }
+
+    /// Returns the token at the given address, loading it from the server
+    /// if it's missing from memory, otherwise retrieving it from its ERC-20
+    /// contract and sharing it through the server.
+    ///
+    /// Sharing the retrieved token flushes all the pending changes.
+    pub async fn get_or_retrieve<P, N>(
+        &mut self,
+        provider: &P,
+        address: Address,
+    ) -> Result<&Token, Error>
+    where
+        P: Erc20ProviderExt<N>,
+        N: Network,
+    {
+        let chain_id = provider
+            .get_chain_id()
+            .await
+            .map_err(|err| Error::new(address.into(), err))?;
+
+        let loaded = self
+            .load(chain_id, address)
+            .await
+            .map_err(|err| Error::new(address.into(), err))?
+            .is_some();
+
+        if !loaded {
+            let token = retrieve_chain_token(provider, chain_id, address).await?;
+
+            self.tokens.insert(chain_id, token);
+            self.flush()
+                .await
+                .map_err(|err| Error::new(address.into(), err))?;
+        }
+
+        self.tokens.try_get(chain_id, TokenId::Address(address))
+    }
+
+    /// Writes the tokens inserted, mutated or removed, and the canonical
+    /// tokens chosen, since the last flush to the server.
+    ///
+    /// On failure, the changes are kept to be written by the next flush.
+    pub async fn flush(&mut self) -> RedisResult<()> {
+        if self.tokens.is_clean() {
+            return Ok(());
+        }
+
+        let mut pipe = redis::pipe();
+
+        for (chain_id, address, token) in self.tokens.changed_tokens() {
+            let key = self.key(chain_id);
+            let field = address.to_checksum(None);
+
+            match token {
+                Some(token) => {
+                    let value = serde_json::to_string(token).map_err(|err| {
+                        redis::RedisError::from((
+                            redis::ErrorKind::TypeError,
+                            "Failed to serialize the token",
+                            err.to_string(),
+                        ))
+                    })?;
+
+                    pipe.hset(key, field, value).ignore();
+                }
+                None => {
+                    pipe.hdel(key, field).ignore();
+                }
+            }
+        }
+
+        for (chain_id, symbol, address) in self.tokens.changed_canonicals() {
+            pipe.hset(
+                format!("{}:canonical", self.key(chain_id)),
+                symbol,
+                address.to_checksum(None),
+            )
+            .ignore();
+        }
+
+        pipe.query_async::<()>(&mut self.conn).await?;
+        self.tokens.mark_written();
+
+        Ok(())
+    }
+
+    /// Returns the key of the hash holding the tokens of the given chain.
+    fn key(&self, chain_id: u64) -> String {
+        format!("{}:{chain_id}", self.prefix)
+    }
+
+    /// Returns the chain of the given hash key, and whether it holds the
+    /// canonical tokens rather than the tokens.
+    fn parse_key(&self, key: &str) -> Option<(u64, bool)> {
+        let key = key.strip_prefix(&self.prefix)?.strip_prefix(':')?;
+
+        match key.split_once(':') {
+            Some((chain_id, "canonical")) => Some((chain_id.parse().ok()?, true)),
+            Some(_) => None,
+            None => Some((key.parse().ok()?, false)),
+        }
+    }
+}
+
+impl Debug for RedisTokenStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisTokenStore")
+            .field("prefix", &self.prefix)
+            .field("tokens", &self.tokens)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Escapes the glob metacharacters of the given key prefix, for it to be
+/// matched literally by `SCAN`.
+fn escape_pattern(prefix: &str) -> String {
+    let mut escaped = String::with_capacity(prefix.len());
+
+    for c in prefix.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Decodes a token stored as JSON.
+fn decode(chain_id: u64, value: &str) -> Option<Token> {
+    let token = serde_json::from_str::<Token>(value).ok()?;
+
+    Some(Token { chain_id, ..token })
+}
+
+forward_to_write_back!(RedisTokenStore, tokens);
@@ -0,0 +1,11 @@
+use std::collections::BTreeMap;
+
+use crate::Token;
+
+/// A snapshot of the tokens of a store, by chain id.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StoreSnapshot {
+    /// The tokens, by chain id, sorted by address.
+    pub tokens: BTreeMap<u64, Vec<Token>>,
+}
@@ -0,0 +1,66 @@
+use std::ops::Deref;
+
+use crate::{Token, TokenMetadata};
+
+/// A mutable reference to a token of a [`TokenStore`](super::TokenStore).
+///
+/// Only the fields not indexed by the store can be changed through it, the
+/// symbol and address being changed with
+/// [`TokenStoreMut::update`](super::TokenStoreMut::update) instead.
+#[derive(Debug)]
+pub struct TokenMut<'a> {
+    token: &'a mut Token,
+    // A copy of the token kept up to date, like the token of an entry
+    mirror: Option<&'a mut Token>,
+}
+
+impl<'a> TokenMut<'a> {
+    /// Creates a mutable reference to the given token.
+    pub const fn new(token: &'a mut Token) -> Self {
+        Self {
+            token,
+            mirror: None,
+        }
+    }
+
+    /// Creates a mutable reference to the given token, applying the changes
+    /// to its copy as well.
+    pub(crate) const fn mirrored(token: TokenMut<'a>, mirror: &'a mut Token) -> Self {
+        Self {
+            token: token.token,
+            mirror: Some(mirror),
+        }
+    }
+
+    /// Sets the token decimals.
+    pub const fn set_decimals(&mut self, decimals: u8) {
+        self.token.decimals = decimals;
+
+        if let Some(mirror) = &mut self.mirror {
+            mirror.decimals = decimals;
+        }
+    }
+
+    /// Sets the token metadata.
+    pub fn set_metadata(&mut self, metadata: Option<TokenMetadata>) {
+        if let Some(mirror) = &mut self.mirror {
+            mirror.metadata = metadata.clone();
+        }
+
+        self.token.metadata = metadata;
+    }
+
+    /// Converts the mutable reference into a shared one, with the same
+    /// lifetime.
+    pub const fn into_ref(self) -> &'a Token {
+        self.token
+    }
+}
+
+impl Deref for TokenMut<'_> {
+    type Target = Token;
+
+    fn deref(&self) -> &Self::Target {
+        self.token
+    }
+}
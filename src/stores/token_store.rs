@@ -2,34 +2,146 @@ use std::ops::Deref;
 
 use alloy::primitives::Address;
 
-use crate::{Token, TokenId};
+use crate::{error::InternalError, Error, Token, TokenId};
 
-use super::{Entry, StoreIter};
+use super::{Entry, StoreIter, TokenMut};
 
 /// A [`Token`] store
+///
+/// Several tokens of a chain can share the same symbol. A symbol shared by
+/// several tokens only identifies its canonical token, if one has been
+/// chosen with [`set_canonical`](Self::set_canonical), and is ambiguous
+/// otherwise.
 pub trait TokenStore<'a>: Sized {
     /// a reference to a token.
     type Item: Deref<Target = Token>;
 
-    /// Returns the value corresponding to the given id.
+    /// Returns the value corresponding to the given id, or `None` if it's
+    /// missing or an ambiguous symbol.
     fn get(&'a self, chain_id: u64, id: TokenId) -> Option<Self::Item>;
 
-    /// Returns the mutable value corresponding to the given id.
-    fn get_mut(&mut self, chain_id: u64, id: TokenId) -> Option<&mut Token>;
+    /// Returns the mutable value corresponding to the given id, or `None` if
+    /// it's missing or an ambiguous symbol.
+    ///
+    /// The token symbol and address can't be changed through it, see
+    /// [`TokenStoreMut::update`] instead.
+    fn get_mut(&mut self, chain_id: u64, id: TokenId) -> Option<TokenMut<'_>>;
 
-    /// Inserts a token into the store.
+    /// Returns the mutable token at the given address, once found or
+    /// inserted by an [`Entry`].
+    ///
+    /// Stores hiding some of their tokens, like expired ones, must return it
+    /// anyway, as the entry already holds it. Defaults to
+    /// [`get_mut`](Self::get_mut).
+    fn entry_mut(&mut self, chain_id: u64, address: Address) -> Option<TokenMut<'_>> {
+        self.get_mut(chain_id, TokenId::Address(address))
+    }
+
+    /// Inserts a token into the store, replacing the token with the same
+    /// address if any.
+    ///
+    /// The token must be of the given chain, or of an unknown chain (0) to
+    /// be set to it: inserting a token of another chain is a bug, caught by a
+    /// debug assertion.
     fn insert(&mut self, chain_id: u64, token: Token);
 
+    /// Returns the number of tokens in the store.
+    fn len(&'a self) -> usize {
+        self.addresses(None).len()
+    }
+
+    /// Returns `true` if the store contains no tokens.
+    fn is_empty(&'a self) -> bool {
+        self.len() == 0
+    }
+
     /// Returns `true` if the store contains a value for the specified `id`.
     fn contains(&self, chain_id: u64, id: TokenId) -> bool;
 
+    /// Returns the addresses of the tokens with the given symbol, in their
+    /// insertion order.
+    ///
+    /// No addresses are returned by default, for stores looking symbols up
+    /// in their own [`get`](Self::get).
+    fn symbol_addresses(&self, _chain_id: u64, _symbol: &str) -> Vec<Address> {
+        Vec::new()
+    }
+
+    /// Returns the address of the canonical token with the given symbol, if
+    /// one has been chosen.
+    fn canonical(&self, chain_id: u64, symbol: &str) -> Option<Address>;
+
+    /// Chooses the token identified by the given symbol when it's shared by
+    /// several tokens.
+    ///
+    /// The choice must be kept by the store, even when the token isn't
+    /// present yet, for [`canonical`](Self::canonical) to return it.
+    fn set_canonical(&mut self, chain_id: u64, symbol: String, address: Address);
+
     /// Returns the symbols from all the tokens in the store.
     fn symbols(&'a self, chain_id: Option<u64>) -> Vec<String>;
 
     /// Returns the addresses from all the tokens in the store.
     fn addresses(&'a self, chain_id: Option<u64>) -> Vec<Address>;
 
+    /// Returns the chains of the tokens in the store, in ascending order.
+    fn chain_ids(&'a self) -> Vec<u64>;
+
+    /// Returns all the tokens with the given symbol.
+    ///
+    /// The tokens are cloned, so that the store never hands out several
+    /// items at once.
+    fn candidates(&'a self, chain_id: u64, symbol: &str) -> Vec<Token> {
+        self.symbol_addresses(chain_id, symbol)
+            .into_iter()
+            .filter_map(|address| {
+                self.get(chain_id, TokenId::Address(address))
+                    .map(|token| token.clone())
+            })
+            .collect()
+    }
+
+    /// Returns the address of the token identified by the given id,
+    /// failing if it's an unknown or ambiguous symbol.
+    fn resolve(&self, chain_id: u64, id: TokenId) -> Result<Address, Error> {
+        let symbol = match id {
+            TokenId::Address(address) => return Ok(address),
+            TokenId::Symbol(ref symbol) => symbol,
+        };
+
+        let addresses = self.symbol_addresses(chain_id, symbol);
+
+        match addresses.as_slice() {
+            [] => Err(InternalError::NotInStore(symbol.clone())),
+            [address] => Ok(*address),
+            _ => match self.canonical(chain_id, symbol) {
+                Some(address) if addresses.contains(&address) => Ok(address),
+                _ => Err(InternalError::Ambiguous(symbol.clone(), addresses)),
+            },
+        }
+        .map_err(|err| Error::new(id, err))
+    }
+
+    /// Returns the value corresponding to the given id, failing if it's
+    /// missing or an ambiguous symbol.
+    fn try_get(&'a self, chain_id: u64, id: TokenId) -> Result<Self::Item, Error> {
+        if let Some(token) = self.get(chain_id, id.clone()) {
+            return Ok(token);
+        }
+
+        // Explains why the token is missing
+        self.resolve(chain_id, id.clone())?;
+
+        Err(Error::new(
+            id.clone(),
+            InternalError::NotInStore(id.to_string()),
+        ))
+    }
+
     /// Gets the entry for the given token id.
+    ///
+    /// The entry is occupied if the token can be got with
+    /// [`get_mut`](Self::get_mut).
     fn entry(&'a mut self, chain_id: u64, id: TokenId) -> Entry<'a, Self> {
         Entry::new(chain_id, id, self)
     }
@@ -55,6 +167,48 @@ pub trait TokenStore<'a>: Sized {
             self.insert(chain_id, arbitrum::WETH.to_owned());
             self.insert(chain_id, arbitrum::USDC.to_owned());
             self.insert(chain_id, arbitrum::USDT.to_owned());
+
+            // Prefer native USDC over its bridged version
+            self.set_canonical(
+                chain_id,
+                arbitrum::USDC.symbol.clone(),
+                arbitrum::USDC.address,
+            );
         }
     }
 }
+
+/// A [`TokenStore`] whose tokens can be removed.
+pub trait TokenStoreMut<'a>: TokenStore<'a> {
+    /// Removes a token from the store, returning it if it was present.
+    fn remove(&mut self, chain_id: u64, id: TokenId) -> Option<Token>;
+
+    /// Retains only the tokens for which `f` returns `true`.
+    fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(u64, &Token) -> bool;
+
+    /// Removes all the tokens from the store, keeping the canonical tokens
+    /// choices.
+    fn clear(&mut self) {
+        self.retain(|_, _| false);
+    }
+
+    /// Updates a token of the store, keeping it reachable from its new
+    /// symbol and address if they changed.
+    ///
+    /// Returns `false` if the token isn't present.
+    fn update<F>(&mut self, chain_id: u64, id: TokenId, f: F) -> bool
+    where
+        F: FnOnce(&mut Token),
+    {
+        let Some(mut token) = self.remove(chain_id, id) else {
+            return false;
+        };
+
+        f(&mut token);
+        self.insert(chain_id, token);
+
+        true
+    }
+}
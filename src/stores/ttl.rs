@@ -0,0 +1,375 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+use alloy::{network::Network, primitives::Address};
+use futures::{Stream, StreamExt};
+
+use crate::{Erc20ProviderExt, Error, Token, TokenId};
+
+use super::{remove_each, TokenMut, TokenStore, TokenStoreMut};
+
+/// The source of the current time of a [`TtlTokenStore`].
+pub trait Clock: Debug {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+}
+
+/// The [`Clock`] of the system, used by default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A [`TokenStore`] wrapper expiring its tokens after a given time-to-live.
+///
+/// Expired tokens are hidden, so they appear vacant in
+/// [`entry`](TokenStore::entry) and are retrieved again by
+/// [`Erc20ProviderExt::get_token`]. They can also be refreshed in the
+/// background using [`refresh`](Self::refresh).
+#[derive(Debug)]
+pub struct TtlTokenStore<S, C = SystemClock> {
+    inner: S,
+    ttl: Duration,
+    clock: C,
+    fetched_at: HashMap<(u64, Address), Instant>,
+    // The number of fetch times above which the ones of the tokens evicted
+    // from the wrapped store are pruned
+    prune_at: usize,
+}
+
+/// The minimum number of fetch times kept before pruning them.
+const MIN_PRUNE_AT: usize = 64;
+
+impl<S> TtlTokenStore<S> {
+    /// Creates a new [`TtlTokenStore`] expiring the tokens inserted in the
+    /// given store after `ttl`.
+    ///
+    /// The tokens already present in the store never expire.
+    pub fn new(inner: S, ttl: Duration) -> Self {
+        Self::with_clock(inner, ttl, SystemClock)
+    }
+}
+
+impl<S, C> TtlTokenStore<S, C>
+where
+    C: Clock,
+{
+    /// Creates a new [`TtlTokenStore`] expiring the tokens inserted in the
+    /// given store after `ttl`, as measured by the given clock.
+    pub fn with_clock(inner: S, ttl: Duration, clock: C) -> Self {
+        Self {
+            inner,
+            ttl,
+            clock,
+            fetched_at: HashMap::new(),
+            prune_at: MIN_PRUNE_AT,
+        }
+    }
+
+    /// Returns the time-to-live of the tokens.
+    pub const fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the time at which the given token was fetched.
+    pub fn fetched_at(&self, chain_id: u64, address: Address) -> Option<Instant> {
+        self.fetched_at.get(&(chain_id, address)).copied()
+    }
+
+    /// Returns the wrapped store.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn is_expired(&self, chain_id: u64, address: Address) -> bool {
+        self.fetched_at
+            .get(&(chain_id, address))
+            .is_some_and(|fetched_at| self.has_expired(*fetched_at))
+    }
+
+    fn has_expired(&self, fetched_at: Instant) -> bool {
+        self.clock.now().saturating_duration_since(fetched_at) >= self.ttl
+    }
+
+    /// Returns the expired tokens, which may have been removed from the
+    /// wrapped store.
+    fn expired_tokens(&self) -> impl Iterator<Item = (u64, Address)> + '_ {
+        self.fetched_at
+            .iter()
+            .filter(|(_, fetched_at)| self.has_expired(**fetched_at))
+            .map(|(key, _)| *key)
+    }
+}
+
+impl<S, C> TtlTokenStore<S, C>
+where
+    S: for<'a> TokenStore<'a>,
+    C: Clock,
+{
+    /// Returns the addresses of the expired tokens of the given chain.
+    pub fn expired(&self, chain_id: u64) -> Vec<Address> {
+        self.expired_tokens()
+            .filter(|(token_chain_id, address)| {
+                *token_chain_id == chain_id
+                    && self.inner.contains(chain_id, TokenId::Address(*address))
+            })
+            .map(|(_, address)| address)
+            .collect()
+    }
+
+    /// Retrieves the expired tokens of the given chain again, through a
+    /// single Multicall3 request, and returns how many were refreshed.
+    ///
+    /// The store is only locked before and after the retrieval, and the
+    /// tokens failing to be retrieved stay expired. Only the symbol, decimals
+    /// and name of the tokens are refreshed, the rest of their metadata, like
+    /// the logo and tags imported from a token list, being kept.
+    pub async fn refresh<P, N>(
+        store: &RwLock<Self>,
+        provider: &P,
+        chain_id: u64,
+    ) -> Result<usize, Error>
+    where
+        P: Erc20ProviderExt<N>,
+        N: Network,
+    {
+        let expired = store.read().unwrap().expired(chain_id);
+        let tokens = provider.retrieve_tokens(&expired).await?;
+
+        let mut store = store.write().unwrap();
+        let mut refreshed = 0;
+
+        for token in tokens.into_iter().flatten() {
+            store.refresh_token(chain_id, token);
+            refreshed += 1;
+        }
+
+        Ok(refreshed)
+    }
+
+    /// Replaces the symbol, decimals and name of a token with the ones
+    /// retrieved again, keeping the rest of its metadata, like its logo and
+    /// tags, and resets its fetch time.
+    fn refresh_token(&mut self, chain_id: u64, token: Token) {
+        let current = self
+            .inner
+            .get(chain_id, TokenId::Address(token.address))
+            .map(|current| current.clone());
+
+        let token = match current {
+            Some(mut current) => {
+                let name = token.name().map(str::to_string);
+
+                current.symbol = token.symbol;
+                current.decimals = token.decimals;
+
+                match &mut current.metadata {
+                    Some(metadata) => metadata.name = name.or(metadata.name.take()),
+                    None => current.metadata = token.metadata,
+                }
+
+                current
+            }
+            None => token,
+        };
+
+        self.insert(chain_id, token);
+    }
+
+    /// Refreshes the expired tokens of the given chain on each tick of the
+    /// given stream, like a runtime interval, until it ends.
+    ///
+    /// The returned stream yields the result of each refresh, see
+    /// [`refresh`](Self::refresh), and is meant to be driven by a background
+    /// task handling the failures. Failed refreshes are retried on the next
+    /// tick.
+    pub fn refresh_on<'s, P, N, T>(
+        store: &'s RwLock<Self>,
+        provider: &'s P,
+        chain_id: u64,
+        ticks: T,
+    ) -> impl Stream<Item = Result<usize, Error>> + 's
+    where
+        P: Erc20ProviderExt<N>,
+        N: Network,
+        T: Stream + 's,
+    {
+        ticks.then(move |_| Self::refresh(store, provider, chain_id))
+    }
+}
+
+impl<'a, S, C> TokenStore<'a> for TtlTokenStore<S, C>
+where
+    S: TokenStore<'a>,
+    C: Clock,
+{
+    type Item = S::Item;
+
+    fn get(&'a self, chain_id: u64, id: TokenId) -> Option<Self::Item> {
+        let address = self.resolve(chain_id, id).ok()?;
+
+        if self.is_expired(chain_id, address) {
+            return None;
+        }
+
+        self.inner.get(chain_id, TokenId::Address(address))
+    }
+
+    fn get_mut(&mut self, chain_id: u64, id: TokenId) -> Option<TokenMut<'_>> {
+        let address = self.resolve(chain_id, id).ok()?;
+
+        if self.is_expired(chain_id, address) {
+            return None;
+        }
+
+        self.inner.get_mut(chain_id, TokenId::Address(address))
+    }
+
+    fn entry_mut(&mut self, chain_id: u64, address: Address) -> Option<TokenMut<'_>> {
+        self.inner.entry_mut(chain_id, address)
+    }
+
+    fn insert(&mut self, chain_id: u64, token: Token) {
+        self.fetched_at
+            .insert((chain_id, token.address), self.clock.now());
+        self.inner.insert(chain_id, token);
+
+        // The wrapped store may evict tokens, like an LRU store
+        if self.fetched_at.len() > self.prune_at {
+            let inner = &self.inner;
+
+            self.fetched_at.retain(|(chain_id, address), _| {
+                inner.contains(*chain_id, TokenId::Address(*address))
+            });
+            self.prune_at = (self.fetched_at.len() * 2).max(MIN_PRUNE_AT);
+        }
+    }
+
+    fn contains(&self, chain_id: u64, id: TokenId) -> bool {
+        self.resolve(chain_id, id).is_ok_and(|address| {
+            !self.is_expired(chain_id, address)
+                && self.inner.contains(chain_id, TokenId::Address(address))
+        })
+    }
+
+    fn symbol_addresses(&self, chain_id: u64, symbol: &str) -> Vec<Address> {
+        self.inner.symbol_addresses(chain_id, symbol)
+    }
+
+    fn canonical(&self, chain_id: u64, symbol: &str) -> Option<Address> {
+        self.inner.canonical(chain_id, symbol)
+    }
+
+    fn set_canonical(&mut self, chain_id: u64, symbol: String, address: Address) {
+        self.inner.set_canonical(chain_id, symbol, address);
+    }
+
+    fn symbols(&'a self, chain_id: Option<u64>) -> Vec<String> {
+        let is_hidden = |chain_id: u64, symbol: &str| {
+            self.symbol_addresses(chain_id, symbol)
+                .into_iter()
+                .all(|address| self.is_expired(chain_id, address))
+        };
+
+        match chain_id {
+            Some(chain_id) => self
+                .inner
+                .symbols(Some(chain_id))
+                .into_iter()
+                .filter(|symbol| !is_hidden(chain_id, symbol))
+                .collect(),
+            // The symbols are listed once per chain, so each hidden one is
+            // removed once per chain
+            None => {
+                let chain_ids = self
+                    .expired_tokens()
+                    .map(|(chain_id, _)| chain_id)
+                    .collect::<HashSet<_>>();
+
+                let hidden = chain_ids.into_iter().flat_map(|chain_id| {
+                    self.inner
+                        .symbols(Some(chain_id))
+                        .into_iter()
+                        .filter(move |symbol| is_hidden(chain_id, symbol))
+                });
+
+                remove_each(self.inner.symbols(None), hidden)
+            }
+        }
+    }
+
+    fn addresses(&'a self, chain_id: Option<u64>) -> Vec<Address> {
+        match chain_id {
+            Some(chain_id) => self
+                .inner
+                .addresses(Some(chain_id))
+                .into_iter()
+                .filter(|address| !self.is_expired(chain_id, *address))
+                .collect(),
+            // The addresses are listed once per chain, so each expired one is
+            // removed once per chain
+            None => {
+                let expired = self
+                    .expired_tokens()
+                    .filter(|(chain_id, address)| {
+                        self.inner.contains(*chain_id, TokenId::Address(*address))
+                    })
+                    .map(|(_, address)| address);
+
+                remove_each(self.inner.addresses(None), expired)
+            }
+        }
+    }
+
+    fn chain_ids(&'a self) -> Vec<u64> {
+        self.inner
+            .chain_ids()
+            .into_iter()
+            .filter(|chain_id| !self.addresses(Some(*chain_id)).is_empty())
+            .collect()
+    }
+}
+
+impl<'a, S, C> TokenStoreMut<'a> for TtlTokenStore<S, C>
+where
+    S: TokenStoreMut<'a>,
+    C: Clock,
+{
+    fn remove(&mut self, chain_id: u64, id: TokenId) -> Option<Token> {
+        let token = self.inner.remove(chain_id, id)?;
+
+        self.fetched_at.remove(&(chain_id, token.address));
+
+        Some(token)
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear();
+        self.fetched_at.clear();
+    }
+
+    fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(u64, &Token) -> bool,
+    {
+        let fetched_at = &mut self.fetched_at;
+
+        self.inner.retain(|chain_id, token| {
+            let keep = f(chain_id, token);
+
+            if !keep {
+                fetched_at.remove(&(chain_id, token.address));
+            }
+
+            keep
+        });
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashSet;
+
+use alloy::primitives::Address;
+
+use crate::{Token, TokenId};
+
+use super::{BasicTokenStore, TokenMut, TokenStore, TokenStoreMut};
+
+/// The in-memory tokens of a store persisting them elsewhere, keeping track
+/// of the tokens and canonical choices changed since they were last written.
+#[derive(Debug, Default)]
+pub(super) struct WriteBack {
+    tokens: BasicTokenStore,
+    dirty: HashSet<(u64, Address)>,
+    dirty_canonicals: HashSet<(u64, String)>,
+}
+
+impl WriteBack {
+    /// Creates an empty [`WriteBack`].
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns whether the token at the given address has been changed
+    /// since the last write.
+    pub(super) fn is_dirty(&self, chain_id: u64, address: Address) -> bool {
+        self.dirty.contains(&(chain_id, address))
+    }
+
+    /// Replaces the tokens and canonical choices with the given ones, as
+    /// last written, unless they have been changed since.
+    ///
+    /// The tokens and canonical choices missing from the given ones have
+    /// been removed, and are dropped unless they have been changed since.
+    #[cfg(feature = "redis-store")]
+    pub(super) fn replace(&mut self, tokens: Vec<Token>, canonicals: Vec<(u64, String, Address)>) {
+        let present = tokens
+            .iter()
+            .map(|token| (token.chain_id, token.address))
+            .collect::<HashSet<_>>();
+        let dirty = &self.dirty;
+
+        self.tokens.retain(|chain_id, token| {
+            let key = (chain_id, token.address);
+
+            dirty.contains(&key) || present.contains(&key)
+        });
+
+        let present = canonicals
+            .iter()
+            .map(|(chain_id, symbol, _)| (*chain_id, symbol.clone()))
+            .collect::<HashSet<_>>();
+        let dirty = &self.dirty_canonicals;
+
+        self.tokens.retain_canonicals(|chain_id, symbol| {
+            let key = (chain_id, symbol.to_string());
+
+            dirty.contains(&key) || present.contains(&key)
+        });
+
+        for token in tokens {
+            self.load(token);
+        }
+
+        for (chain_id, symbol, address) in canonicals {
+            self.load_canonical(chain_id, symbol, address);
+        }
+    }
+
+    /// Adds a token as last written, unless it has been changed since.
+    pub(super) fn load(&mut self, token: Token) {
+        if !self.is_dirty(token.chain_id, token.address) {
+            self.tokens.insert(token.chain_id, token);
+        }
+    }
+
+    /// Chooses a canonical token as last written, unless the choice has
+    /// been changed since.
+    pub(super) fn load_canonical(&mut self, chain_id: u64, symbol: String, address: Address) {
+        if !self.dirty_canonicals.contains(&(chain_id, symbol.clone())) {
+            self.tokens.set_canonical(chain_id, symbol, address);
+        }
+    }
+
+    /// Returns whether nothing has been changed since the last write.
+    pub(super) fn is_clean(&self) -> bool {
+        self.dirty.is_empty() && self.dirty_canonicals.is_empty()
+    }
+
+    /// Returns the tokens changed since the last write, `None` for the
+    /// removed ones.
+    pub(super) fn changed_tokens(&self) -> impl Iterator<Item = (u64, Address, Option<&Token>)> {
+        self.dirty.iter().map(|(chain_id, address)| {
+            (
+                *chain_id,
+                *address,
+                self.tokens.get(*chain_id, TokenId::Address(*address)),
+            )
+        })
+    }
+
+    /// Returns the canonical choices changed since the last write.
+    pub(super) fn changed_canonicals(&self) -> impl Iterator<Item = (u64, &str, Address)> {
+        self.dirty_canonicals
+            .iter()
+            .filter_map(|(chain_id, symbol)| {
+                self.tokens
+                    .canonical(*chain_id, symbol)
+                    .map(|address| (*chain_id, symbol.as_str(), address))
+            })
+    }
+
+    /// Forgets the changes, once they have been written.
+    pub(super) fn mark_written(&mut self) {
+        self.dirty.clear();
+        self.dirty_canonicals.clear();
+    }
+}
+
+impl<'a> TokenStore<'a> for WriteBack {
+    type Item = &'a Token;
+
+    fn get(&'a self, chain_id: u64, id: TokenId) -> Option<Self::Item> {
+        self.tokens.get(chain_id, id)
+    }
+
+    fn get_mut(&mut self, chain_id: u64, id: TokenId) -> Option<TokenMut<'_>> {
+        let token = self.tokens.get_mut(chain_id, id)?;
+
+        self.dirty.insert((chain_id, token.address));
+
+        Some(token)
+    }
+
+    fn insert(&mut self, chain_id: u64, token: Token) {
+        self.dirty.insert((chain_id, token.address));
+        self.tokens.insert(chain_id, token);
+    }
+
+    fn len(&'a self) -> usize {
+        self.tokens.len()
+    }
+
+    fn contains(&self, chain_id: u64, id: TokenId) -> bool {
+        self.tokens.contains(chain_id, id)
+    }
+
+    fn symbol_addresses(&self, chain_id: u64, symbol: &str) -> Vec<Address> {
+        self.tokens.symbol_addresses(chain_id, symbol)
+    }
+
+    fn canonical(&self, chain_id: u64, symbol: &str) -> Option<Address> {
+        self.tokens.canonical(chain_id, symbol)
+    }
+
+    fn set_canonical(&mut self, chain_id: u64, symbol: String, address: Address) {
+        self.dirty_canonicals.insert((chain_id, symbol.clone()));
+        self.tokens.set_canonical(chain_id, symbol, address);
+    }
+
+    fn symbols(&'a self, chain_id: Option<u64>) -> Vec<String> {
+        self.tokens.symbols(chain_id)
+    }
+
+    fn addresses(&'a self, chain_id: Option<u64>) -> Vec<Address> {
+        self.tokens.addresses(chain_id)
+    }
+
+    fn chain_ids(&'a self) -> Vec<u64> {
+        self.tokens.chain_ids()
+    }
+}
+
+impl TokenStoreMut<'_> for WriteBack {
+    fn remove(&mut self, chain_id: u64, id: TokenId) -> Option<Token> {
+        let token = self.tokens.remove(chain_id, id)?;
+
+        self.dirty.insert((chain_id, token.address));
+
+        Some(token)
+    }
+
+    fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(u64, &Token) -> bool,
+    {
+        let dirty = &mut self.dirty;
+
+        self.tokens.retain(|chain_id, token| {
+            let keep = f(chain_id, token);
+
+            if !keep {
+                dirty.insert((chain_id, token.address));
+            }
+
+            keep
+        });
+    }
+}
+
+/// Implements [`TokenStore`] and [`TokenStoreMut`] for a store holding its
+/// tokens in the given [`WriteBack`] field.
+macro_rules! forward_to_write_back {
+    ($store:ty, $field:ident) => {
+        impl<'a> $crate::TokenStore<'a> for $store {
+            type Item = &'a $crate::Token;
+
+            fn get(&'a self, chain_id: u64, id: $crate::TokenId) -> Option<Self::Item> {
+                $crate::TokenStore::get(&self.$field, chain_id, id)
+            }
+
+            fn get_mut(
+                &mut self,
+                chain_id: u64,
+                id: $crate::TokenId,
+            ) -> Option<$crate::TokenMut<'_>> {
+                $crate::TokenStore::get_mut(&mut self.$field, chain_id, id)
+            }
+
+            fn insert(&mut self, chain_id: u64, token: $crate::Token) {
+                $crate::TokenStore::insert(&mut self.$field, chain_id, token);
+            }
+
+            fn len(&'a self) -> usize {
+                $crate::TokenStore::len(&self.$field)
+            }
+
+            fn contains(&self, chain_id: u64, id: $crate::TokenId) -> bool {
+                $crate::TokenStore::contains(&self.$field, chain_id, id)
+            }
+
+            fn symbol_addresses(
+                &self,
+                chain_id: u64,
+                symbol: &str,
+            ) -> Vec<alloy::primitives::Address> {
+                $crate::TokenStore::symbol_addresses(&self.$field, chain_id, symbol)
+            }
+
+            fn canonical(&self, chain_id: u64, symbol: &str) -> Option<alloy::primitives::Address> {
+                $crate::TokenStore::canonical(&self.$field, chain_id, symbol)
+            }
+
+            fn set_canonical(
+                &mut self,
+                chain_id: u64,
+                symbol: String,
+                address: alloy::primitives::Address,
+            ) {
+                $crate::TokenStore::set_canonical(&mut self.$field, chain_id, symbol, address);
+            }
+
+            fn symbols(&'a self, chain_id: Option<u64>) -> Vec<String> {
+                $crate::TokenStore::symbols(&self.$field, chain_id)
+            }
+
+            fn addresses(&'a self, chain_id: Option<u64>) -> Vec<alloy::primitives::Address> {
+                $crate::TokenStore::addresses(&self.$field, chain_id)
+            }
+
+            fn chain_ids(&'a self) -> Vec<u64> {
+                $crate::TokenStore::chain_ids(&self.$field)
+            }
+        }
+
+        impl $crate::TokenStoreMut<'_> for $store {
+            fn remove(&mut self, chain_id: u64, id: $crate::TokenId) -> Option<$crate::Token> {
+                $crate::TokenStoreMut::remove(&mut self.$field, chain_id, id)
+            }
+
+            fn retain<F>(&mut self, f: F)
+            where
+                F: FnMut(u64, &$crate::Token) -> bool,
+            {
+                $crate::TokenStoreMut::retain(&mut self.$field, f);
+            }
+        }
+    };
+}
+
+pub(super) use forward_to_write_back;
@@ -1,41 +1,198 @@
-use alloy::primitives::{Address, U256};
-use bigdecimal::{
-    num_bigint::{BigInt, Sign},
-    BigDecimal,
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    str::FromStr,
 };
 
+use alloy::primitives::{Address, I256, U256};
+use bigdecimal::BigDecimal;
+
+use crate::{amount, error::InternalError, Error, Rounding};
+
 /// A token.
+///
+/// Tokens are identified by their chain id and address: tokens sharing a
+/// symbol are different if deployed at different addresses or on different
+/// chains.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Token {
+    /// The chain the token is deployed on.
+    ///
+    /// A token is inserted into a store under its own chain id. A chain id of
+    /// 0 means that the chain is unknown, and is then set by the store.
+    pub chain_id: u64,
     /// The token address.
+    #[cfg_attr(feature = "serde", serde(with = "crate::checksum"))]
     pub address: Address,
     /// The token symbol.
     pub symbol: String,
     /// The token decimals
     pub decimals: u8,
+    /// The token metadata, if known.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub metadata: Option<TokenMetadata>,
+}
+
+/// Optional information about a [`Token`], as retrieved from its contract
+/// or imported from a token list.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenMetadata {
+    /// The token name.
+    pub name: Option<String>,
+    /// The URI of the token logo.
+    pub logo_uri: Option<String>,
+    /// The token tags, like the ones of a token list.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub tags: Vec<String>,
+    /// The standards and behaviors detected on the token contract.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub capabilities: TokenCapabilities,
+}
+
+/// The standards and behaviors of a token contract.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenCapabilities {
+    /// Whether the token supports [EIP-2612] permits.
+    ///
+    /// [EIP-2612]: https://eips.ethereum.org/EIPS/eip-2612
+    pub permit: bool,
+    /// Whether the token is an [ERC-4626] vault.
+    ///
+    /// [ERC-4626]: https://eips.ethereum.org/EIPS/eip-4626
+    pub erc4626: bool,
+    /// Whether the token takes a fee on transfers.
+    ///
+    /// This can't be detected without simulating a transfer, so it's never
+    /// set by [`Erc20ProviderExt::retrieve_token`](crate::Erc20ProviderExt::retrieve_token).
+    pub fee_on_transfer: bool,
 }
 
 impl Token {
-    /// Creates a new token.
-    pub const fn new(address: Address, symbol: String, decimals: u8) -> Self {
+    /// Creates a new token, deployed on the given chain.
+    pub const fn new(chain_id: u64, address: Address, symbol: String, decimals: u8) -> Self {
         Self {
+            chain_id,
             address,
             symbol,
             decimals,
+            metadata: None,
         }
     }
 
+    /// Sets the chain of a token inserted into a store under the given chain
+    /// id, which must be its own one unless it's unknown.
+    pub(crate) fn on_chain(mut self, chain_id: u64) -> Self {
+        debug_assert!(
+            self.chain_id == 0 || self.chain_id == chain_id,
+            "token {} of chain {} inserted under chain {chain_id}",
+            self.address,
+            self.chain_id,
+        );
+
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// Sets the token metadata.
+    pub fn with_metadata(mut self, metadata: TokenMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Returns the token name, if known.
+    pub fn name(&self) -> Option<&str> {
+        self.metadata.as_ref()?.name.as_deref()
+    }
+
     /// Gets the token balance as a [`BigDecimal`]
     pub fn get_balance(&self, amount: U256) -> BigDecimal {
-        BigDecimal::from((
-            BigInt::from_bytes_be(Sign::Plus, &amount.to_be_bytes::<{ U256::BYTES }>()),
-            self.decimals as i64,
-        ))
+        amount::to_decimal(amount, self.decimals)
+    }
+
+    /// Gets a signed token amount, like a balance change, as a
+    /// [`BigDecimal`].
+    pub fn get_signed_balance(&self, amount: I256) -> BigDecimal {
+        let (sign, abs) = amount.into_sign_and_abs();
+        let balance = self.get_balance(abs);
+
+        if sign.is_negative() {
+            -balance
+        } else {
+            balance
+        }
+    }
+
+    /// Scales the given decimal amount to the token smallest unit, the
+    /// inverse of [`get_balance`](Self::get_balance).
+    ///
+    /// Fails if the amount is negative or overflows a `U256`, or if it has
+    /// more decimals than the token and `rounding` is [`Rounding::Exact`].
+    pub fn from_decimal(&self, amount: &BigDecimal, rounding: Rounding) -> Result<U256, Error> {
+        amount::from_decimal(amount, self.decimals, rounding)
+            .map_err(|err| Error::new(self.address.into(), err))
+    }
+
+    /// Parses a human readable amount, like `12.5` or `1.5e3`, to the token
+    /// smallest unit, see [`from_decimal`](Self::from_decimal).
+    ///
+    /// `max`, whatever its case, is parsed as `U256::MAX`, like used for
+    /// unlimited approvals.
+    pub fn parse_amount(&self, amount: &str, rounding: Rounding) -> Result<U256, Error> {
+        let amount = amount.trim();
+
+        if amount.eq_ignore_ascii_case("max") {
+            return Ok(U256::MAX);
+        }
+
+        let decimal = BigDecimal::from_str(amount).map_err(|_| {
+            Error::new(
+                self.address.into(),
+                InternalError::InvalidAmount(amount.to_string()),
+            )
+        })?;
+
+        self.from_decimal(&decimal, rounding)
+    }
+}
+
+impl Token {
+    /// Returns the token identity.
+    const fn key(&self) -> (u64, Address) {
+        (self.chain_id, self.address)
     }
 }
 
 impl PartialEq for Token {
     fn eq(&self, other: &Self) -> bool {
-        self.symbol == other.symbol
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Token {}
+
+impl Hash for Token {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialOrd for Token {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Token {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
     }
 }
@@ -0,0 +1,337 @@
+use std::fmt::{self, Alignment, Display};
+
+use alloy::primitives::{Sign as I256Sign, I256, U256, U512};
+use bigdecimal::{
+    num_bigint::{BigInt, Sign},
+    BigDecimal, RoundingMode, ToPrimitive,
+};
+
+use crate::{error::InternalError, Error, Rounding, Token};
+
+/// An amount of a given token, held in the token smallest unit.
+///
+/// The arithmetic is exact and checked: it fails on overflows, and when
+/// mixing amounts of different tokens.
+///
+/// It's displayed with the token decimals and symbol, like `1,234.56 USDC`.
+/// The precision can be given as usual, like `{:.2}`, and the alternate
+/// flag, like `{:#}`, omits the symbol. The amount is padded to the width,
+/// like `{:<16}`, and aligned to the right by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAmount<'a> {
+    token: &'a Token,
+    raw: U256,
+}
+
+impl<'a> TokenAmount<'a> {
+    /// Creates an amount of the given token, expressed in its smallest unit.
+    pub const fn new(token: &'a Token, raw: U256) -> Self {
+        Self { token, raw }
+    }
+
+    /// Creates a zero amount of the given token.
+    pub const fn zero(token: &'a Token) -> Self {
+        Self::new(token, U256::ZERO)
+    }
+
+    /// Creates an amount of the given token from a decimal value, failing if
+    /// it has more decimals than the token.
+    pub fn from_decimal(token: &'a Token, amount: &BigDecimal) -> Result<Self, Error> {
+        let raw = token.from_decimal(amount, Rounding::Exact)?;
+
+        Ok(Self::new(token, raw))
+    }
+
+    /// Creates an amount of the given token from a float, rounded to the
+    /// token decimals.
+    pub fn from_f64(token: &'a Token, amount: f64) -> Result<Self, Error> {
+        let amount = BigDecimal::try_from(amount)
+            .map_err(|_| error(token, InternalError::InvalidAmount(amount.to_string())))?;
+        let raw = token.from_decimal(&amount, Rounding::HalfEven)?;
+
+        Ok(Self::new(token, raw))
+    }
+
+    /// Parses an amount of the given token from a decimal string, like
+    /// `1234.56`, allowing thousands separators and the token symbol as a
+    /// suffix, like `1,234.56 USDC`, see [`Token::parse_amount`].
+    pub fn parse(token: &'a Token, amount: &str) -> Result<Self, Error> {
+        let digits = amount
+            .trim()
+            .strip_suffix(token.symbol.as_str())
+            .unwrap_or(amount)
+            .replace(',', "");
+
+        let raw = token.parse_amount(&digits, Rounding::Exact)?;
+
+        Ok(Self::new(token, raw))
+    }
+
+    /// Returns the token of the amount.
+    pub const fn token(&self) -> &'a Token {
+        self.token
+    }
+
+    /// Returns the amount in the token smallest unit.
+    pub const fn raw(&self) -> U256 {
+        self.raw
+    }
+
+    /// Returns whether the amount is zero.
+    pub fn is_zero(&self) -> bool {
+        self.raw.is_zero()
+    }
+
+    /// Returns the amount as a decimal value, scaled using the token
+    /// decimals.
+    pub fn to_decimal(&self) -> BigDecimal {
+        self.token.get_balance(self.raw)
+    }
+
+    /// Returns the amount as a float, which may lose precision.
+    pub fn to_f64(&self) -> f64 {
+        self.to_decimal().to_f64().unwrap_or(f64::INFINITY)
+    }
+
+    /// Adds the given amount of the same token.
+    pub fn checked_add(&self, other: &Self) -> Result<Self, Error> {
+        check_token(self.token, other.token)?;
+
+        let raw = self
+            .raw
+            .checked_add(other.raw)
+            .ok_or_else(|| self.overflow(self.to_decimal() + other.to_decimal()))?;
+
+        Ok(Self::new(self.token, raw))
+    }
+
+    /// Subtracts the given amount of the same token.
+    pub fn checked_sub(&self, other: &Self) -> Result<Self, Error> {
+        check_token(self.token, other.token)?;
+
+        let raw = self.raw.checked_sub(other.raw).ok_or_else(|| {
+            error(
+                self.token,
+                InternalError::NegativeAmount(self.to_decimal() - other.to_decimal()),
+            )
+        })?;
+
+        Ok(Self::new(self.token, raw))
+    }
+
+    /// Multiplies the amount by `numerator / denominator`, rounding down,
+    /// without overflowing on the intermediate product.
+    pub fn checked_mul_ratio(&self, numerator: U256, denominator: U256) -> Result<Self, Error> {
+        if denominator.is_zero() {
+            return Err(error(self.token, InternalError::ZeroDenominator));
+        }
+
+        let product = self.raw.widening_mul::<256, 4, 512, 8>(numerator) / U512::from(denominator);
+
+        let raw = U256::checked_from_limbs_slice(product.as_limbs()).ok_or_else(|| {
+            self.overflow(BigDecimal::new(
+                BigInt::from_bytes_be(Sign::Plus, &product.to_be_bytes::<{ U512::BYTES }>()),
+                self.token.decimals.into(),
+            ))
+        })?;
+
+        Ok(Self::new(self.token, raw))
+    }
+
+    /// Returns the signed change from the given previous amount of the same
+    /// token to this one.
+    pub fn checked_delta(&self, previous: &Self) -> Result<TokenDelta<'a>, Error> {
+        check_token(self.token, previous.token)?;
+
+        let raw = if self.raw >= previous.raw {
+            I256::checked_from_sign_and_abs(I256Sign::Positive, self.raw - previous.raw)
+        } else {
+            I256::checked_from_sign_and_abs(I256Sign::Negative, previous.raw - self.raw)
+        };
+
+        let raw = raw.ok_or_else(|| self.overflow(self.to_decimal() - previous.to_decimal()))?;
+
+        Ok(TokenDelta::new(self.token, raw))
+    }
+
+    fn overflow(&self, amount: BigDecimal) -> Error {
+        error(self.token, InternalError::Overflow(amount))
+    }
+}
+
+impl TokenAmount<'_> {
+    /// Formats the amount with thousands separators, rounded to the given
+    /// number of decimals if any, and followed by the token symbol unless
+    /// requested otherwise.
+    fn format(&self, precision: Option<usize>, with_symbol: bool) -> String {
+        let amount = match precision {
+            Some(precision) => self
+                .to_decimal()
+                .with_scale_round(precision as i64, RoundingMode::HalfEven),
+            None => self.to_decimal().normalized(),
+        };
+
+        let amount = amount.to_plain_string();
+        let (integer, fraction) = amount.split_at(amount.find('.').unwrap_or(amount.len()));
+        let mut formatted = String::with_capacity(amount.len() * 2);
+
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                formatted.push(',');
+            }
+
+            formatted.push(digit);
+        }
+
+        formatted.push_str(fraction);
+
+        if with_symbol {
+            formatted.push(' ');
+            formatted.push_str(&self.token.symbol);
+        }
+
+        formatted
+    }
+}
+
+impl Display for TokenAmount<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        pad(f, &self.format(f.precision(), !f.alternate()))
+    }
+}
+
+impl From<TokenAmount<'_>> for BigDecimal {
+    fn from(amount: TokenAmount<'_>) -> Self {
+        amount.to_decimal()
+    }
+}
+
+/// A signed amount of a given token, like a balance change, held in the
+/// token smallest unit.
+///
+/// It's displayed like a [`TokenAmount`], prefixed by `-` when negative, or
+/// by `+` when positive and the `+` flag is given, like `{:+}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenDelta<'a> {
+    token: &'a Token,
+    raw: I256,
+}
+
+impl<'a> TokenDelta<'a> {
+    /// Creates a signed amount of the given token, expressed in its smallest
+    /// unit.
+    pub const fn new(token: &'a Token, raw: I256) -> Self {
+        Self { token, raw }
+    }
+
+    /// Returns the token of the amount.
+    pub const fn token(&self) -> &'a Token {
+        self.token
+    }
+
+    /// Returns the amount in the token smallest unit.
+    pub const fn raw(&self) -> I256 {
+        self.raw
+    }
+
+    /// Returns whether the amount is zero.
+    pub const fn is_zero(&self) -> bool {
+        self.raw.is_zero()
+    }
+
+    /// Returns whether the amount is negative.
+    pub const fn is_negative(&self) -> bool {
+        self.raw.is_negative()
+    }
+
+    /// Returns the absolute value of the amount.
+    pub fn unsigned_abs(&self) -> TokenAmount<'a> {
+        TokenAmount::new(self.token, self.raw.unsigned_abs())
+    }
+
+    /// Returns the amount as a signed decimal value, scaled using the token
+    /// decimals.
+    pub fn to_decimal(&self) -> BigDecimal {
+        self.token.get_signed_balance(self.raw)
+    }
+
+    /// Adds the given signed amount of the same token.
+    pub fn checked_add(&self, other: &Self) -> Result<Self, Error> {
+        check_token(self.token, other.token)?;
+
+        let raw = self.raw.checked_add(other.raw).ok_or_else(|| {
+            error(
+                self.token,
+                InternalError::Overflow(self.to_decimal() + other.to_decimal()),
+            )
+        })?;
+
+        Ok(Self::new(self.token, raw))
+    }
+}
+
+impl Display for TokenDelta<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.is_negative() {
+            "-"
+        } else if f.sign_plus() && !self.is_zero() {
+            "+"
+        } else {
+            ""
+        };
+
+        let amount = self.unsigned_abs().format(f.precision(), !f.alternate());
+
+        pad(f, &format!("{sign}{amount}"))
+    }
+}
+
+impl From<TokenDelta<'_>> for BigDecimal {
+    fn from(delta: TokenDelta<'_>) -> Self {
+        delta.to_decimal()
+    }
+}
+
+/// Writes the given formatted amount, padded to the requested width and
+/// aligned to the right by default, like numbers.
+///
+/// [`fmt::Formatter::pad`] isn't used, as it would truncate the amount to the
+/// precision, which is the number of decimals here.
+fn pad(f: &mut fmt::Formatter<'_>, amount: &str) -> fmt::Result {
+    let len = amount.chars().count();
+    let padding = f.width().unwrap_or(0).saturating_sub(len);
+
+    let (before, after) = match f.align() {
+        Some(Alignment::Left) => (0, padding),
+        Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+        Some(Alignment::Right) | None => (padding, 0),
+    };
+
+    let fill = f.fill();
+
+    for _ in 0..before {
+        write!(f, "{fill}")?;
+    }
+
+    f.write_str(amount)?;
+
+    for _ in 0..after {
+        write!(f, "{fill}")?;
+    }
+
+    Ok(())
+}
+
+/// Fails if the given tokens are different.
+fn check_token(token: &Token, other: &Token) -> Result<(), Error> {
+    if token != other {
+        return Err(error(token, InternalError::TokenMismatch(other.address)));
+    }
+
+    Ok(())
+}
+
+/// Creates an [`Error`] related to the given token.
+fn error(token: &Token, source: InternalError) -> Error {
+    Error::new(token.address.into(), source)
+}
@@ -38,3 +38,28 @@ impl FromStr for TokenId {
         Ok(TokenId::Symbol(s.to_string()))
     }
 }
+
+/// Serializes the id as a string: the checksummed address, or the symbol.
+#[cfg(feature = "serde")]
+impl serde::Serialize for TokenId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TokenId::Symbol(s) => serializer.serialize_str(s),
+            TokenId::Address(a) => serializer.serialize_str(&a.to_checksum(None)),
+        }
+    }
+}
+
+/// Deserializes the id from a string, as an address if it's a `0x` prefixed
+/// address, or as a symbol otherwise.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TokenId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+
+        match s.strip_prefix("0x").and_then(|_| s.parse::<Address>().ok()) {
+            Some(address) => Ok(TokenId::Address(address)),
+            None => Ok(TokenId::Symbol(s)),
+        }
+    }
+}
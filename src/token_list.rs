@@ -0,0 +1,403 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::{Token, TokenMetadata, TokenStore};
+
+/// A token list, following the [Uniswap Token Lists] standard.
+///
+/// [Uniswap Token Lists]: https://tokenlists.org
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenList {
+    /// The name of the list.
+    pub name: String,
+    /// The time at which the list was last updated, as an ISO 8601 string.
+    pub timestamp: String,
+    /// The version of the list.
+    pub version: TokenListVersion,
+    /// The tokens of the list.
+    pub tokens: Vec<TokenInfo>,
+    /// The keywords describing the list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keywords: Option<Vec<String>>,
+    /// The definitions of the tags used by the tokens, by tag id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<BTreeMap<String, TagDefinition>>,
+    /// The URI of the list logo.
+    #[serde(rename = "logoURI", default, skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+}
+
+/// The semantic version of a [`TokenList`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TokenListVersion {
+    /// Incremented when tokens are removed.
+    pub major: u32,
+    /// Incremented when tokens are added.
+    pub minor: u32,
+    /// Incremented when tokens details are changed.
+    pub patch: u32,
+}
+
+/// A token of a [`TokenList`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    /// The chain the token is deployed on.
+    pub chain_id: u64,
+    /// The token address.
+    #[serde(with = "crate::checksum")]
+    pub address: Address,
+    /// The token name.
+    pub name: String,
+    /// The token symbol.
+    pub symbol: String,
+    /// The token decimals.
+    pub decimals: u8,
+    /// The URI of the token logo.
+    #[serde(rename = "logoURI", default, skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+    /// The ids of the token tags.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// Additional information about the token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// The definition of a tag used by the tokens of a [`TokenList`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagDefinition {
+    /// The tag name.
+    pub name: String,
+    /// The tag description.
+    pub description: String,
+}
+
+/// Token list related errors.
+#[derive(thiserror::Error, Debug)]
+pub enum TokenListError {
+    /// The list is not valid JSON, or doesn't match the token list structure.
+    #[error("Failed to parse token list: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The list doesn't comply with the token list schema.
+    #[error("Invalid token list: {0}")]
+    InvalidList(&'static str),
+    /// A token doesn't comply with the token list schema.
+    #[error("Invalid token {0} on chain {1}: {2}")]
+    InvalidToken(Address, u64, &'static str),
+    /// A token is listed twice.
+    #[error("Duplicate token {0} on chain {1}")]
+    Duplicate(Address, u64),
+}
+
+impl TokenList {
+    /// Parses and validates a JSON token list.
+    pub fn from_json(json: &str) -> Result<Self, TokenListError> {
+        let list = serde_json::from_str::<Self>(json)?;
+
+        list.validate()?;
+
+        Ok(list)
+    }
+
+    /// Validates and serializes the list to JSON.
+    pub fn to_json(&self) -> Result<String, TokenListError> {
+        self.validate()?;
+
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Creates a list from the tokens of the given chains present in the
+    /// store, timestamped with the current time.
+    ///
+    /// The symbol of the tokens whose name is unknown is used instead. Their
+    /// tags are not exported, as the list doesn't define them. The list is
+    /// validated, failing if a token doesn't comply with the schema.
+    pub fn from_store<'a, S>(
+        name: impl Into<String>,
+        chain_ids: &[u64],
+        store: &'a S,
+    ) -> Result<Self, TokenListError>
+    where
+        S: TokenStore<'a>,
+    {
+        let tokens = chain_ids
+            .iter()
+            .flat_map(|chain_id| {
+                store.iter(*chain_id).map(|token| TokenInfo {
+                    chain_id: *chain_id,
+                    address: token.address,
+                    name: token.name().unwrap_or(&token.symbol).to_string(),
+                    symbol: token.symbol.clone(),
+                    decimals: token.decimals,
+                    logo_uri: token
+                        .metadata
+                        .as_ref()
+                        .and_then(|metadata| metadata.logo_uri.clone()),
+                    tags: None,
+                    extensions: None,
+                })
+            })
+            .collect();
+
+        let list = Self {
+            name: name.into(),
+            timestamp: now(),
+            version: TokenListVersion::default(),
+            tokens,
+            keywords: None,
+            tags: None,
+            logo_uri: None,
+        };
+
+        list.validate()?;
+
+        Ok(list)
+    }
+
+    /// Inserts the tokens of the list into the given store.
+    pub fn insert_into<'a, S>(&self, store: &mut S)
+    where
+        S: TokenStore<'a>,
+    {
+        for token in &self.tokens {
+            store.insert(token.chain_id, token.into());
+        }
+    }
+
+    /// Checks that the list complies with the token list schema, and
+    /// doesn't contain duplicate tokens.
+    pub fn validate(&self) -> Result<(), TokenListError> {
+        let invalid = |reason| Err(TokenListError::InvalidList(reason));
+
+        if !is_text(&self.name, 1, 30, " ") {
+            return invalid("the name must be between 1 and 30 word characters or spaces long");
+        }
+
+        if !is_timestamp(&self.timestamp) {
+            return invalid("the timestamp must be an ISO 8601 date and time");
+        }
+
+        if self.logo_uri.as_deref().is_some_and(|uri| !is_uri(uri)) {
+            return invalid("the logo URI must be a URI");
+        }
+
+        if let Some(keywords) = &self.keywords {
+            let unique = keywords.iter().collect::<HashSet<_>>().len() == keywords.len();
+
+            if keywords.len() > 20
+                || !unique
+                || !keywords.iter().all(|keyword| is_text(keyword, 1, 20, " "))
+            {
+                return invalid(
+                    "the list can't have more than 20 unique keywords, of between 1 and 20 word characters or spaces",
+                );
+            }
+        }
+
+        if let Some(tags) = &self.tags {
+            if tags.len() > 20 {
+                return invalid("the list can't define more than 20 tags");
+            }
+
+            for (id, tag) in tags {
+                if !is_text(id, 1, 10, "")
+                    || !is_text(&tag.name, 1, 20, " ")
+                    || !is_text(&tag.description, 1, 200, " .,:")
+                {
+                    return invalid("the tags must have a valid id, name and description");
+                }
+            }
+        }
+
+        if self.tokens.is_empty() || self.tokens.len() > 10_000 {
+            return invalid("the list must contain between 1 and 10000 tokens");
+        }
+
+        let mut seen = HashSet::with_capacity(self.tokens.len());
+
+        for token in &self.tokens {
+            token.validate()?;
+
+            if let Some(tags) = &token.tags {
+                let defined = tags.iter().all(|tag| {
+                    self.tags
+                        .as_ref()
+                        .is_some_and(|definitions| definitions.contains_key(tag))
+                });
+
+                if !defined {
+                    return Err(TokenListError::InvalidToken(
+                        token.address,
+                        token.chain_id,
+                        "the tags must be defined by the list",
+                    ));
+                }
+            }
+
+            if !seen.insert((token.chain_id, token.address)) {
+                return Err(TokenListError::Duplicate(token.address, token.chain_id));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TokenInfo {
+    fn validate(&self) -> Result<(), TokenListError> {
+        let invalid = |reason| {
+            Err(TokenListError::InvalidToken(
+                self.address,
+                self.chain_id,
+                reason,
+            ))
+        };
+
+        if self.chain_id == 0 {
+            return invalid("the chain id must be positive");
+        }
+
+        if self.name.is_empty() || self.name.chars().count() > 60 {
+            return invalid("the name must be between 1 and 60 characters long");
+        }
+
+        if self.symbol.is_empty()
+            || self.symbol.chars().count() > 20
+            || self.symbol.contains(char::is_whitespace)
+        {
+            return invalid("the symbol must be between 1 and 20 non-whitespace characters long");
+        }
+
+        if self.logo_uri.as_deref().is_some_and(|uri| !is_uri(uri)) {
+            return invalid("the logo URI must be a URI");
+        }
+
+        if let Some(tags) = &self.tags {
+            if tags.len() > 10 || !tags.iter().all(|tag| is_text(tag, 1, 10, "")) {
+                return invalid("the token can't have more than 10 tags, of valid ids");
+            }
+        }
+
+        if let Some(extensions) = &self.extensions {
+            if !is_extensions(extensions, 0) {
+                return invalid(
+                    "the extensions must have at most 10 keys of up to 40 word characters, and short values nested at most twice",
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether the given text has between `min` and `max` characters,
+/// all ASCII word characters or part of `extra`, like the `^[\w ]+$`
+/// patterns of the schema.
+fn is_text(text: &str, min: usize, max: usize, extra: &str) -> bool {
+    (min..=max).contains(&text.chars().count())
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || extra.contains(c))
+}
+
+/// Returns whether the given text looks like an ISO 8601 date and time, like
+/// `2024-01-01T00:00:00.000Z`.
+fn is_timestamp(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    let digits = |range: std::ops::Range<usize>| {
+        bytes
+            .get(range)
+            .is_some_and(|digits| digits.iter().all(u8::is_ascii_digit))
+    };
+
+    digits(0..4)
+        && bytes.get(4) == Some(&b'-')
+        && digits(5..7)
+        && bytes.get(7) == Some(&b'-')
+        && digits(8..10)
+        && matches!(bytes.get(10), Some(b'T' | b't'))
+        && digits(11..13)
+        && bytes.get(13) == Some(&b':')
+        && digits(14..16)
+        && bytes.get(16) == Some(&b':')
+        && digits(17..19)
+}
+
+/// Returns whether the given text is a URI, starting with a scheme like
+/// `https:` or `ipfs:`.
+fn is_uri(text: &str) -> bool {
+    text.split_once(':').is_some_and(|(scheme, rest)| {
+        !rest.is_empty()
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    })
+}
+
+/// Returns whether the given token extensions comply with the schema, which
+/// allows them to be nested up to twice.
+fn is_extensions(extensions: &serde_json::Map<String, serde_json::Value>, depth: usize) -> bool {
+    extensions.len() <= 10
+        && extensions.iter().all(|(key, value)| {
+            is_text(key, 1, 40, "")
+                && match value {
+                    serde_json::Value::String(value) => value.chars().count() <= 42,
+                    serde_json::Value::Object(value) => {
+                        depth < 2 && is_extensions(value, depth + 1)
+                    }
+                    serde_json::Value::Array(_) => false,
+                    _ => true,
+                }
+        })
+}
+
+impl From<&TokenInfo> for Token {
+    fn from(info: &TokenInfo) -> Self {
+        Token::new(
+            info.chain_id,
+            info.address,
+            info.symbol.clone(),
+            info.decimals,
+        )
+        .with_metadata(TokenMetadata {
+            name: Some(info.name.clone()),
+            logo_uri: info.logo_uri.clone(),
+            tags: info.tags.clone().unwrap_or_default(),
+            ..TokenMetadata::default()
+        })
+    }
+}
+
+/// Formats the current time as an ISO 8601 UTC timestamp.
+fn now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    // Converts the days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = secs / 86_400 + 719_468;
+    let era = days / 146_097;
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.000Z",
+        secs / 3_600 % 24,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
@@ -0,0 +1,49 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use alloy::{
+    primitives::{Bytes, U256},
+    sol,
+    sol_types::SolValue,
+};
+
+sol! {
+    interface Multicall3 {
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+    }
+}
+
+/// Encodes the results of an aggregated Multicall3 call, given the encoded
+/// return data of each call, `None` being a failed call.
+pub fn multicall_results(results: &[Option<Vec<u8>>]) -> Bytes {
+    results
+        .iter()
+        .map(|result| Multicall3::Result {
+            success: result.is_some(),
+            returnData: result.clone().unwrap_or_default().into(),
+        })
+        .collect::<Vec<_>>()
+        .abi_encode()
+        .into()
+}
+
+/// Returns the results of the calls retrieving a token, its metadata failing
+/// to be retrieved.
+pub fn token_calls(symbol: &str, decimals: u8) -> [Option<Vec<u8>>; 5] {
+    [
+        Some(symbol.to_string().abi_encode()),
+        Some(U256::from(decimals).abi_encode()),
+        None,
+        None,
+        None,
+    ]
+}
+
+/// Encodes the Multicall3 results of a token retrieval, see [`token_calls`].
+pub fn token_results(symbol: &str, decimals: u8) -> Bytes {
+    multicall_results(&token_calls(symbol, decimals))
+}
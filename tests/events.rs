@@ -0,0 +1,267 @@
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{address, Address, LogData, U256, U64},
+    providers::{mock::Asserter, ProviderBuilder},
+    rpc::types::{Filter, Log},
+    sol_types::SolValue,
+};
+use alloy_erc20::{mainnet::USDC, BasicTokenStore, Erc20ProviderExt, TokenStore};
+use bigdecimal::BigDecimal;
+use futures::StreamExt;
+use std::str::FromStr;
+
+mod common;
+
+use common::multicall_results;
+
+fn transfer_log(from: Address, to: Address, value: U256, block_number: u64) -> Log {
+    Log {
+        inner: alloy::primitives::Log {
+            address: USDC.address,
+            data: LogData::new_unchecked(
+                vec![
+                    // Transfer(address,address,uint256)
+                    "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+                        .parse()
+                        .unwrap(),
+                    from.into_word(),
+                    to.into_word(),
+                ],
+                value.abi_encode().into(),
+            ),
+        },
+        block_number: Some(block_number),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_get_transfer_events() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let mut store = BasicTokenStore::new();
+    store.insert(1, USDC.clone());
+
+    let from = address!("0000000000000000000000000000000000000001");
+    let to = address!("0000000000000000000000000000000000000002");
+
+    // eth_chainId
+    asserter.push_success(&U64::from(1));
+    // The whole range is refused, and is then queried in two chunks
+    asserter.push_failure_msg("block range is too wide");
+    asserter.push_success(&vec![transfer_log(from, to, U256::from(1_500_000), 10)]);
+    asserter.push_success(&vec![transfer_log(to, from, U256::from(500_000), 20)]);
+
+    let events = provider
+        .get_transfer_events(Filter::new().from_block(0).to_block(20), &mut store)
+        .await
+        .unwrap();
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].from, from);
+    assert_eq!(events[0].value, BigDecimal::from_str("1.5").unwrap());
+    assert_eq!(events[1].block_number, Some(20));
+}
+
+#[tokio::test]
+async fn test_get_logs_chunked_failure() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    // Only the failures about the size of the query split its range
+    for message in ["rate limited", "daily request limit exceeded"] {
+        asserter.push_failure_msg(message);
+        asserter.push_success(&Vec::<Log>::new());
+
+        assert!(provider
+            .get_logs_chunked(&Filter::new().from_block(0).to_block(20))
+            .await
+            .is_err());
+        assert_eq!(asserter.read_q().len(), 1);
+
+        asserter.write_q().clear();
+    }
+}
+
+#[tokio::test]
+async fn test_get_logs_chunked_tags() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    // eth_blockNumber, for the missing upper bound
+    asserter.push_success(&U64::from(20));
+    asserter.push_success(&vec![transfer_log(
+        address!("0000000000000000000000000000000000000001"),
+        address!("0000000000000000000000000000000000000002"),
+        U256::from(1),
+        10,
+    )]);
+
+    let logs = provider
+        .get_logs_chunked(&Filter::new().from_block(BlockNumberOrTag::Earliest))
+        .await
+        .unwrap();
+
+    assert_eq!(logs.len(), 1);
+}
+
+#[tokio::test]
+async fn test_watch_transfers() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let mut store = BasicTokenStore::new();
+    store.insert(1, USDC.clone());
+
+    let holder = address!("0000000000000000000000000000000000000001");
+    let other = address!("0000000000000000000000000000000000000002");
+
+    // eth_chainId
+    asserter.push_success(&U64::from(1));
+    // eth_newFilter, for outgoing then incoming transfers
+    asserter.push_success(&U256::from(1));
+    asserter.push_success(&U256::from(2));
+    // eth_getFilterChanges
+    asserter.push_success(&vec![transfer_log(
+        holder,
+        other,
+        U256::from(1_000_000),
+        10,
+    )]);
+    asserter.push_success(&vec![transfer_log(
+        other,
+        holder,
+        U256::from(2_000_000),
+        11,
+    )]);
+
+    let mut transfers = provider
+        .watch_transfers(vec![holder], None, store)
+        .await
+        .unwrap();
+
+    let outgoing = transfers.next().await.unwrap().unwrap();
+    let incoming = transfers.next().await.unwrap().unwrap();
+
+    assert_eq!(outgoing.to, other);
+    assert_eq!(outgoing.value, BigDecimal::from(1));
+    assert_eq!(incoming.from, other);
+    assert!(!incoming.removed);
+}
+
+#[tokio::test]
+async fn test_watch_transfers_without_holders() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    // No filter is installed, as it would match the transfers of everyone
+    let mut transfers = provider
+        .watch_transfers(vec![], None, BasicTokenStore::new())
+        .await
+        .unwrap();
+
+    assert!(transfers.next().await.is_none());
+
+    // No tokens is the same, as a filter without addresses matches any token
+    let mut transfers = provider
+        .watch_transfers(
+            vec![address!("0000000000000000000000000000000000000001")],
+            Some(vec![]),
+            BasicTokenStore::new(),
+        )
+        .await
+        .unwrap();
+
+    assert!(transfers.next().await.is_none());
+    assert!(asserter.read_q().is_empty());
+}
+
+#[tokio::test]
+async fn test_watch_transfers_retry() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let holder = address!("0000000000000000000000000000000000000001");
+    let other = address!("0000000000000000000000000000000000000002");
+
+    // eth_chainId
+    asserter.push_success(&U64::from(1));
+    // eth_newFilter, for outgoing then incoming transfers
+    asserter.push_success(&U256::from(1));
+    asserter.push_success(&U256::from(2));
+    // eth_getFilterChanges, then the failing retrieval of USDC
+    asserter.push_success(&vec![transfer_log(
+        holder,
+        other,
+        U256::from(1_000_000),
+        10,
+    )]);
+    asserter.push_success(&Vec::<Log>::new());
+    asserter.push_failure_msg("rate limited");
+    // eth_chainId, the chain id being still known
+    asserter.push_success(&U64::from(1));
+
+    let mut transfers = provider
+        .watch_transfers(vec![holder], None, BasicTokenStore::new())
+        .await
+        .unwrap();
+
+    assert!(transfers.next().await.unwrap().is_err());
+
+    // The transfer is yielded once its token is retrieved, on the next poll
+    asserter.push_success(&Vec::<Log>::new());
+
+    asserter.push_success(&multicall_results(&[
+        Some(U256::from(1).abi_encode()),
+        Some(String::from("USDC").abi_encode()),
+        Some(U256::from(6).abi_encode()),
+        Some(String::from("USD Coin").abi_encode()),
+        None,
+        None,
+    ]));
+
+    let transfer = transfers.next().await.unwrap().unwrap();
+
+    assert_eq!(transfer.to, other);
+    assert_eq!(transfer.value, BigDecimal::from(1));
+}
+
+#[tokio::test]
+async fn test_watch_transfers_retry_limit() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let holder = address!("0000000000000000000000000000000000000001");
+    let other = address!("0000000000000000000000000000000000000002");
+
+    asserter.push_success(&U64::from(1));
+    asserter.push_success(&U256::from(1));
+    asserter.push_success(&U256::from(2));
+    asserter.push_success(
+        &(0..10_001u64)
+            .map(|i| transfer_log(holder, other, U256::from(i), 10))
+            .collect::<Vec<_>>(),
+    );
+    asserter.push_success(&Vec::<Log>::new());
+    asserter.push_failure_msg("rate limited");
+    asserter.push_success(&U64::from(1));
+
+    let mut transfers = provider
+        .watch_transfers(vec![holder], None, BasicTokenStore::new())
+        .await
+        .unwrap();
+
+    assert!(transfers.next().await.unwrap().is_err());
+
+    // Only the latest 10,000 transfers are retried
+    asserter.push_success(&Vec::<Log>::new());
+    let mut results = vec![Some(U256::from(1).abi_encode())];
+    results.extend(common::token_calls("USDC", 6));
+
+    asserter.push_success(&multicall_results(&results));
+
+    let transfer = transfers.next().await.unwrap().unwrap();
+
+    assert_eq!(transfer.value, BigDecimal::from_str("0.000001").unwrap());
+}
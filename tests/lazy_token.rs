@@ -1,7 +1,12 @@
-use alloy::{primitives::address, providers::ProviderBuilder};
-use alloy_erc20::LazyToken;
+use alloy::{
+    eips::BlockId,
+    primitives::{address, bytes, B256, U256, U64},
+    providers::{mock::Asserter, ProviderBuilder},
+};
+use alloy_erc20::{register_native_token, LazyToken, Token, TokenMetadata, NATIVE_TOKEN_ADDRESS};
+use bigdecimal::BigDecimal;
 use dotenv::dotenv;
-use std::env;
+use std::{env, str::FromStr};
 
 #[tokio::test]
 async fn test_lazy_token() {
@@ -18,3 +23,162 @@ async fn test_lazy_token() {
 
     assert_eq!(name, "Dai Stablecoin")
 }
+
+#[tokio::test]
+async fn test_transfer_request() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let usdc = LazyToken::new(
+        address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+        provider,
+    );
+
+    // decimals
+    asserter.push_success(&B256::with_last_byte(6));
+
+    let request = usdc
+        .transfer_request(
+            address!("0000000000000000000000000000000000000001"),
+            BigDecimal::from_str("1.5").unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        request.input.input().unwrap(),
+        &bytes!("a9059cbb0000000000000000000000000000000000000000000000000000000000000001000000000000000000000000000000000000000000000000000000000016e360")
+    );
+}
+
+#[tokio::test]
+async fn test_balance_of_at_pinned_block() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let usdc = LazyToken::new(
+        address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+        provider,
+    );
+
+    let account = address!("0000000000000000000000000000000000000001");
+    let block = BlockId::hash(B256::with_last_byte(1));
+
+    asserter.push_success(&B256::with_last_byte(42));
+
+    assert_eq!(
+        usdc.balance_of_at(account, block).await.unwrap(),
+        U256::from(42)
+    );
+
+    // The result at a pinned block is cached, so no other query is made
+    assert_eq!(
+        usdc.balance_of_at(account, block).await.unwrap(),
+        U256::from(42)
+    );
+}
+
+#[tokio::test]
+async fn test_native_token() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let eth = LazyToken::new(NATIVE_TOKEN_ADDRESS, provider);
+
+    asserter.push_success(&U64::from(1));
+    asserter.push_success(&U256::from(42));
+
+    assert_eq!(eth.symbol().await.unwrap(), "ETH");
+    assert_eq!(eth.name().await.unwrap(), "ETH");
+    assert_eq!(*eth.decimals().await.unwrap(), 18);
+    assert_eq!(
+        eth.balance_of(address!("0000000000000000000000000000000000000002"))
+            .await
+            .unwrap(),
+        U256::from(42)
+    );
+
+    // The native asset has no contract to call
+    assert!(eth.total_supply().await.is_err());
+    assert!(eth
+        .allowance(
+            address!("0000000000000000000000000000000000000002"),
+            address!("0000000000000000000000000000000000000003"),
+        )
+        .await
+        .is_err());
+
+    let err = eth
+        .transfer_request(
+            address!("0000000000000000000000000000000000000002"),
+            U256::from(1),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("native asset has no contract"));
+    assert!(eth
+        .approve(
+            address!("0000000000000000000000000000000000000002"),
+            U256::from(1),
+        )
+        .await
+        .is_err());
+    assert!(asserter.read_q().is_empty());
+}
+
+#[tokio::test]
+async fn test_with_native_token() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let mut pol = Token::new(137, NATIVE_TOKEN_ADDRESS, "POL".to_string(), 18);
+    pol.metadata = Some(TokenMetadata {
+        name: Some("Polygon Ecosystem Token".to_string()),
+        ..TokenMetadata::default()
+    });
+
+    let pol = LazyToken::new(NATIVE_TOKEN_ADDRESS, provider).with_native(pol);
+
+    // No chain id is queried
+    asserter.push_success(&U256::from(42));
+
+    assert_eq!(pol.symbol().await.unwrap(), "POL");
+    assert_eq!(pol.name().await.unwrap(), "Polygon Ecosystem Token");
+    assert_eq!(
+        pol.balance_of(address!("0000000000000000000000000000000000000002"))
+            .await
+            .unwrap(),
+        U256::from(42)
+    );
+}
+
+#[tokio::test]
+async fn test_registered_native_token() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    register_native_token(Token::new(56, NATIVE_TOKEN_ADDRESS, "BNB".to_string(), 18));
+
+    let bnb = LazyToken::new(NATIVE_TOKEN_ADDRESS, provider);
+
+    asserter.push_success(&U64::from(56));
+
+    assert_eq!(bnb.symbol().await.unwrap(), "BNB");
+    assert_eq!(*bnb.decimals().await.unwrap(), 18);
+}
+
+#[tokio::test]
+async fn test_unknown_native_token() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let native = LazyToken::new(NATIVE_TOKEN_ADDRESS, provider);
+
+    asserter.push_success(&U64::from(10));
+
+    let err = native.symbol().await.unwrap_err();
+
+    assert_eq!(err.token, NATIVE_TOKEN_ADDRESS.into());
+    assert!(err.to_string().contains("chain 10 is unknown"));
+}
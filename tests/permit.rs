@@ -0,0 +1,167 @@
+use alloy::{
+    primitives::{address, Bytes, FixedBytes, B256, U256, U64},
+    providers::{mock::Asserter, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+    sol_types::{Eip712Domain, SolStruct, SolValue},
+};
+use alloy_erc20::Erc20ProviderExt;
+use bigdecimal::BigDecimal;
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_sign_permit() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+    let signer = PrivateKeySigner::random();
+
+    let usdc = address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+    let spender = address!("0000000000000000000000000000000000000001");
+
+    // nonces
+    asserter.push_success(&B256::with_last_byte(3));
+
+    // eip712Domain
+    asserter.push_success(
+        &(
+            FixedBytes::<1>::from([0x0f]),
+            String::from("USD Coin"),
+            String::from("2"),
+            U256::from(1),
+            usdc,
+            B256::ZERO,
+            Vec::<U256>::new(),
+        )
+            .abi_encode_params(),
+    );
+
+    let (permit, signature) = provider
+        .sign_permit(usdc, &signer, spender, U256::from(1000), U256::MAX)
+        .await
+        .unwrap();
+
+    assert_eq!(permit.owner, signer.address());
+    assert_eq!(permit.nonce, U256::from(3));
+
+    let domain = Eip712Domain::new(
+        Some("USD Coin".into()),
+        Some("2".into()),
+        Some(U256::from(1)),
+        Some(usdc),
+        None,
+    );
+
+    assert_eq!(
+        signature
+            .recover_address_from_prehash(&permit.eip712_signing_hash(&domain))
+            .unwrap(),
+        signer.address()
+    );
+
+    // permit(address,address,uint256,uint256,uint8,bytes32,bytes32)
+    assert_eq!(permit.calldata(&signature)[..4], [0xd5, 0x05, 0xac, 0xcf]);
+}
+
+#[tokio::test]
+async fn test_sign_permit_decimal_amount() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+    let signer = PrivateKeySigner::random();
+
+    let usdc = address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+
+    // Only the decimals are retrieved to scale the amount
+    asserter.push_success(&B256::with_last_byte(6));
+
+    // nonces
+    asserter.push_success(&B256::ZERO);
+
+    // eip712Domain
+    asserter.push_success(
+        &(
+            FixedBytes::<1>::from([0x0f]),
+            String::from("USD Coin"),
+            String::from("2"),
+            U256::from(1),
+            usdc,
+            B256::ZERO,
+            Vec::<U256>::new(),
+        )
+            .abi_encode_params(),
+    );
+
+    let (permit, _) = provider
+        .sign_permit(
+            usdc,
+            &signer,
+            address!("0000000000000000000000000000000000000001"),
+            BigDecimal::from_str("1.5").unwrap(),
+            U256::MAX,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(permit.value, U256::from(1_500_000));
+}
+
+#[tokio::test]
+async fn test_sign_dai_permit() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+    let signer = PrivateKeySigner::random();
+
+    let dai = address!("6B175474E89094C44Da98b954EedeAC495271d0F");
+    let spender = address!("0000000000000000000000000000000000000001");
+
+    // DAI doesn't implement ERC-5267, its domain is rebuilt from its name
+    let domain = Eip712Domain::new(
+        Some("Dai Stablecoin".into()),
+        Some("1".into()),
+        Some(U256::from(1)),
+        Some(dai),
+        None,
+    );
+
+    // nonces
+    asserter.push_success(&B256::with_last_byte(5));
+    // eip712Domain
+    asserter.push_failure_msg("execution reverted");
+    // name
+    asserter.push_success(&Bytes::from(String::from("Dai Stablecoin").abi_encode()));
+    // DOMAIN_SEPARATOR
+    asserter.push_success(&domain.separator());
+    // chainId
+    asserter.push_success(&U64::from(1));
+
+    let (permit, signature) = provider
+        .sign_dai_permit(dai, &signer, spender, true, U256::MAX)
+        .await
+        .unwrap();
+
+    assert_eq!(permit.holder, signer.address());
+    assert_eq!(permit.nonce, U256::from(5));
+    assert!(permit.allowed);
+
+    assert_eq!(
+        signature
+            .recover_address_from_prehash(&permit.eip712_signing_hash(&domain))
+            .unwrap(),
+        signer.address()
+    );
+
+    // permit(address,address,uint256,uint256,bool,uint8,bytes32,bytes32)
+    assert_eq!(permit.calldata(&signature)[..4], [0x8f, 0xcb, 0xaf, 0x0c]);
+}
+
+#[tokio::test]
+async fn test_eip712_domain_failure() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    // A failure of the node isn't mistaken for a token without ERC-5267
+    asserter.push_failure_msg("rate limited");
+
+    assert!(provider
+        .eip712_domain(address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"))
+        .await
+        .is_err());
+}
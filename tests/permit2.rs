@@ -0,0 +1,101 @@
+use alloy::{
+    primitives::{
+        address,
+        aliases::{U160, U48},
+        U256,
+    },
+    providers::{mock::Asserter, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+    sol_types::{SolStruct, SolValue},
+};
+use alloy_erc20::{
+    mainnet::{DAI, USDC},
+    permit2_domain, BasicTokenStore, Erc20ProviderExt, PermitDetails, PermitSingle, TokenId,
+    TokenPermissions, TokenStore,
+};
+use bigdecimal::BigDecimal;
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_permit2_allowance() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    asserter.push_success(
+        &(U256::from(1000), U256::from(1_700_000_000), U256::from(2)).abi_encode_params(),
+    );
+
+    let allowance = provider
+        .permit2_allowance(
+            USDC.address,
+            address!("0000000000000000000000000000000000000001"),
+            address!("0000000000000000000000000000000000000002"),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(allowance.amount, U160::from(1000));
+    assert_eq!(allowance.nonce, U48::from(2));
+}
+
+#[tokio::test]
+async fn test_sign_permit_single() {
+    let signer = PrivateKeySigner::random();
+    let domain = permit2_domain(1);
+
+    let permit = PermitSingle {
+        details: PermitDetails::new(
+            &USDC,
+            BigDecimal::from_str("12.5").unwrap(),
+            U48::from(1_700_000_000),
+            U48::ZERO,
+        )
+        .unwrap(),
+        spender: address!("0000000000000000000000000000000000000002"),
+        sigDeadline: U256::MAX,
+    };
+
+    assert_eq!(permit.details.amount, U160::from(12_500_000));
+
+    let signature = permit.sign(&signer, &domain).await.unwrap();
+
+    assert_eq!(
+        signature
+            .recover_address_from_prehash(&permit.eip712_signing_hash(&domain))
+            .unwrap(),
+        signer.address()
+    );
+
+    // permit(address,((address,uint160,uint48,uint48),address,uint256),bytes)
+    assert_eq!(
+        permit.calldata(signer.address(), &signature)[..4],
+        [0x2b, 0x67, 0xb5, 0x70]
+    );
+}
+
+#[test]
+fn test_permit2_from_store() {
+    let mut store = BasicTokenStore::new();
+    store.insert(1, USDC.clone());
+
+    let details = PermitDetails::from_store(
+        TokenId::Symbol("USDC".to_string()),
+        1,
+        &store,
+        BigDecimal::from(2),
+        U48::from(1_700_000_000),
+        U48::ZERO,
+    )
+    .unwrap();
+
+    assert_eq!(details.token, USDC.address);
+    assert_eq!(details.amount, U160::from(2_000_000));
+
+    let permissions =
+        TokenPermissions::from_store(USDC.address, 1, &store, BigDecimal::from(1)).unwrap();
+
+    assert_eq!(permissions.amount, U256::from(1_000_000));
+
+    // The token must be in the store
+    assert!(TokenPermissions::from_store(DAI.address, 1, &store, U256::from(1)).is_err());
+}
@@ -0,0 +1,1112 @@
+mod common;
+
+#[cfg(feature = "redb-store")]
+#[test]
+fn test_redb_store() {
+    use alloy::primitives::Address;
+    use alloy_erc20::{
+        mainnet, RedbTokenStore, Token, TokenId, TokenMetadata, TokenStore, TokenStoreMut,
+    };
+    use std::{env, fs, process};
+
+    let path = env::temp_dir().join(format!("alloy-erc20-{}.redb", process::id()));
+
+    let bridged = Token::new(1, Address::with_last_byte(1), "USDC".to_string(), 6);
+
+    {
+        let mut store = RedbTokenStore::open(&path).unwrap();
+
+        store.insert(
+            1,
+            mainnet::DAI.clone().with_metadata(TokenMetadata {
+                name: Some("Dai Stablecoin".to_string()),
+                ..TokenMetadata::default()
+            }),
+        );
+        store.insert(1, mainnet::USDC.clone());
+        store.insert(1, bridged);
+        store.set_canonical(1, "USDC".to_string(), mainnet::USDC.address);
+        store.flush().unwrap();
+
+        // Not flushed, so not persisted
+        store.insert(1, mainnet::WETH.clone());
+    }
+
+    let store = RedbTokenStore::open(&path).unwrap();
+
+    let dai = store.get(1, TokenId::Symbol("DAI".to_string())).unwrap();
+    assert_eq!(dai.address, mainnet::DAI.address);
+    assert_eq!(dai.decimals, 18);
+    assert_eq!(dai.name(), Some("Dai Stablecoin"));
+
+    // The canonical choice is persisted too
+    let usdc = store.get(1, TokenId::Symbol("USDC".to_string())).unwrap();
+    assert_eq!(usdc.address, mainnet::USDC.address);
+
+    assert_eq!(store.iter(1).count(), 3);
+    assert!(!store.contains(42161, TokenId::Address(mainnet::USDC.address)));
+
+    drop(store);
+
+    {
+        let mut store = RedbTokenStore::open(&path).unwrap();
+
+        store.remove(1, TokenId::Symbol("DAI".to_string()));
+        store.flush().unwrap();
+    }
+
+    let store = RedbTokenStore::open(&path).unwrap();
+
+    assert_eq!(store.len(), 2);
+    assert!(!store.contains(1, TokenId::Address(mainnet::DAI.address)));
+
+    drop(store);
+    fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_token_id_serde() {
+    use alloy::primitives::address;
+    use alloy_erc20::TokenId;
+
+    let address = TokenId::Address(address!("6B175474E89094C44Da98b954EedeAC495271d0F"));
+    let symbol = TokenId::Symbol("DAI".to_string());
+
+    let json = serde_json::to_string(&address).unwrap();
+    assert_eq!(json, r#""0x6B175474E89094C44Da98b954EedeAC495271d0F""#);
+    assert_eq!(serde_json::from_str::<TokenId>(&json).unwrap(), address);
+
+    let json = serde_json::to_string(&symbol).unwrap();
+    assert_eq!(json, r#""DAI""#);
+    assert_eq!(serde_json::from_str::<TokenId>(&json).unwrap(), symbol);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_basic_store_snapshot() {
+    use alloy_erc20::{mainnet, BasicTokenStore, StoreSnapshot, TokenId, TokenStore};
+
+    let mut store = BasicTokenStore::new();
+
+    store.insert(1, mainnet::DAI.clone());
+    store.insert(1, mainnet::USDC.clone());
+
+    let json = serde_json::to_string(&store.to_snapshot()).unwrap();
+    assert!(json.contains("0x6B175474E89094C44Da98b954EedeAC495271d0F"));
+
+    let snapshot = serde_json::from_str::<StoreSnapshot>(&json).unwrap();
+    assert_eq!(snapshot, store.to_snapshot());
+
+    let store = BasicTokenStore::from_snapshot(snapshot);
+    let dai = store.get(1, TokenId::Symbol("DAI".to_string())).unwrap();
+    assert_eq!(dai.address, mainnet::DAI.address);
+    assert_eq!(store.iter(1).count(), 2);
+
+    let json = serde_json::to_string(&store).unwrap();
+    let store = serde_json::from_str::<BasicTokenStore>(&json).unwrap();
+    assert!(store.contains(1, TokenId::Address(mainnet::USDC.address)));
+}
+
+#[test]
+fn test_insert_chain_id() {
+    use alloy_erc20::{mainnet, BasicTokenStore, Token, TokenId, TokenStore};
+
+    let mut store = BasicTokenStore::new();
+
+    // A token of an unknown chain is set to the chain it's inserted on
+    store.insert(
+        10,
+        Token {
+            chain_id: 0,
+            ..mainnet::DAI.clone()
+        },
+    );
+
+    assert_eq!(
+        store
+            .get(10, TokenId::Address(mainnet::DAI.address))
+            .unwrap()
+            .chain_id,
+        10
+    );
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "inserted under chain 10")]
+fn test_insert_other_chain() {
+    use alloy_erc20::{mainnet, BasicTokenStore, TokenStore};
+
+    BasicTokenStore::new().insert(10, mainnet::DAI.clone());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_token_serde_chain_id() {
+    use alloy_erc20::{mainnet, Token};
+
+    let json = serde_json::to_string(&*mainnet::DAI).unwrap();
+    assert_eq!(serde_json::from_str::<Token>(&json).unwrap().chain_id, 1);
+
+    // The chain of a token is never guessed
+    let json =
+        r#"{"address":"0x6B175474E89094C44Da98b954EedeAC495271d0F","symbol":"DAI","decimals":18}"#;
+    assert!(serde_json::from_str::<Token>(json).is_err());
+}
+
+#[cfg(feature = "concurrent-store")]
+#[tokio::test]
+async fn test_concurrent_store_get_or_fetch() {
+    use alloy_erc20::{mainnet, ConcurrentTokenStore, Error, TokenId};
+    use futures::future::join_all;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    let store = ConcurrentTokenStore::new();
+    let fetches = AtomicUsize::new(0);
+
+    let tokens = join_all((0..100).map(|_| {
+        store.get_or_fetch(1, TokenId::Address(mainnet::DAI.address), || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+
+            Ok::<_, Error>(mainnet::DAI.clone())
+        })
+    }))
+    .await;
+
+    assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    assert!(tokens
+        .iter()
+        .all(|token| token.as_ref().unwrap().symbol == "DAI"));
+    assert!(store.contains(1, TokenId::Symbol("DAI".to_string())));
+}
+
+#[cfg(feature = "concurrent-store")]
+#[tokio::test]
+async fn test_concurrent_store_failed_fetch() {
+    use alloy_erc20::{mainnet, ConcurrentTokenStore, Error, Token, TokenId};
+    use futures::future::join;
+
+    #[derive(Debug)]
+    struct FetchError;
+
+    impl From<Error> for FetchError {
+        fn from(_: Error) -> Self {
+            Self
+        }
+    }
+
+    let store = ConcurrentTokenStore::new();
+    let dai = TokenId::Address(mainnet::DAI.address);
+
+    // The first fetch fails, the waiting call makes its own attempt
+    let (failed, fetched) = join(
+        store.get_or_fetch(1, dai.clone(), || async {
+            tokio::task::yield_now().await;
+
+            Err::<Token, _>(FetchError)
+        }),
+        store.get_or_fetch(1, dai.clone(), || async {
+            Ok::<_, FetchError>(mainnet::DAI.clone())
+        }),
+    )
+    .await;
+
+    assert!(failed.is_err());
+    assert_eq!(fetched.unwrap().address, mainnet::DAI.address);
+    assert!(store.contains(1, dai));
+
+    // A failed fetch by symbol leaves nothing behind
+    assert!(store
+        .get_or_fetch(1, TokenId::Symbol("USDC".to_string()), || async {
+            Err::<Token, _>(FetchError)
+        })
+        .await
+        .is_err());
+    assert_eq!(store.addresses(None), vec![mainnet::DAI.address]);
+}
+
+#[cfg(feature = "concurrent-store")]
+#[tokio::test]
+async fn test_get_shared_token() {
+    use alloy::{
+        primitives::address,
+        providers::{mock::Asserter, ProviderBuilder},
+    };
+    use alloy_erc20::{ConcurrentTokenStore, Erc20ProviderExt};
+    use futures::future::join_all;
+
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+    let store = ConcurrentTokenStore::new();
+
+    // A single retrieval is expected
+    asserter.push_success(&common::token_results("DAI", 18));
+
+    let dai = address!("6B175474E89094C44Da98b954EedeAC495271d0F");
+
+    let tokens = join_all((0..10).map(|_| provider.get_shared_token(1, dai, &store))).await;
+
+    assert!(tokens
+        .iter()
+        .all(|token| token.as_ref().unwrap().decimals == 18));
+}
+
+#[test]
+fn test_symbol_collision() {
+    use alloy::primitives::address;
+    use alloy_erc20::{arbitrum, BasicTokenStore, Token, TokenId, TokenStore};
+
+    let bridged = Token::new(
+        42161,
+        address!("FF970A61A04b1cA14834A43f5dE4533eBDDB5CC8"),
+        "USDC".to_string(),
+        6,
+    );
+
+    let mut store = BasicTokenStore::new();
+
+    store.insert(42161, bridged.clone());
+    store.insert(42161, arbitrum::USDC.clone());
+
+    let usdc = TokenId::Symbol("USDC".to_string());
+
+    assert_eq!(store.candidates(42161, "USDC").len(), 2);
+    assert!(store.get(42161, usdc.clone()).is_none());
+    assert!(!store.contains(42161, usdc.clone()));
+    assert!(store
+        .try_get(42161, usdc.clone())
+        .unwrap_err()
+        .to_string()
+        .contains("shared by several tokens"));
+
+    // Both tokens are still reachable from their addresses
+    assert!(store.contains(42161, TokenId::Address(bridged.address)));
+    assert!(store.contains(42161, TokenId::Address(arbitrum::USDC.address)));
+
+    store.set_canonical(42161, "USDC".to_string(), arbitrum::USDC.address);
+
+    assert_eq!(
+        store.get(42161, usdc.clone()).unwrap().address,
+        arbitrum::USDC.address
+    );
+
+    // Renaming the bridged token removes it from the USDC candidates
+    store.insert(
+        42161,
+        Token::new(42161, bridged.address, "USDC.e".to_string(), 6),
+    );
+
+    assert_eq!(
+        store.symbol_addresses(42161, "USDC"),
+        [arbitrum::USDC.address]
+    );
+    assert_eq!(
+        store
+            .get(42161, TokenId::Symbol("USDC.e".to_string()))
+            .unwrap()
+            .address,
+        bridged.address
+    );
+}
+
+#[cfg(all(feature = "lru-store", feature = "concurrent-store"))]
+#[tokio::test]
+async fn test_symbol_collision_stores() {
+    use alloy::primitives::address;
+    use alloy_erc20::{
+        arbitrum, ConcurrentTokenStore, Error, LruTokenStore, Token, TokenId, TokenStore,
+    };
+    use std::num::NonZeroUsize;
+
+    let bridged = Token::new(
+        42161,
+        address!("FF970A61A04b1cA14834A43f5dE4533eBDDB5CC8"),
+        "USDC".to_string(),
+        6,
+    );
+    let usdc = TokenId::Symbol("USDC".to_string());
+
+    let mut store = LruTokenStore::new(NonZeroUsize::new(2).unwrap());
+
+    store.insert(42161, bridged.clone());
+    store.insert(42161, arbitrum::USDC.clone());
+
+    assert_eq!(store.candidates(42161, "USDC").len(), 2);
+    assert!(store.get(42161, usdc.clone()).is_none());
+    assert!(store.try_get(42161, usdc.clone()).is_err());
+
+    let store = ConcurrentTokenStore::new();
+
+    store.insert(42161, bridged.clone());
+    store.insert(42161, arbitrum::USDC.clone());
+
+    assert!(store.get(42161, usdc.clone()).is_none());
+    assert!(store
+        .get_or_fetch(42161, usdc.clone(), || async {
+            Ok::<_, Error>(bridged.clone())
+        })
+        .await
+        .is_err());
+    assert_eq!(
+        store
+            .get(42161, TokenId::Address(bridged.address))
+            .unwrap()
+            .address,
+        bridged.address
+    );
+
+    // The canonical token resolves the symbol, without fetching it
+    store.set_canonical(42161, "USDC".to_string(), arbitrum::USDC.address);
+
+    assert_eq!(
+        store.get(42161, usdc.clone()).unwrap().address,
+        arbitrum::USDC.address
+    );
+    assert_eq!(
+        store
+            .get_or_fetch(42161, usdc, || async { Ok::<_, Error>(bridged.clone()) })
+            .await
+            .unwrap()
+            .address,
+        arbitrum::USDC.address
+    );
+}
+
+#[cfg(feature = "lru-store")]
+#[test]
+fn test_lru_store_eviction() {
+    use alloy_erc20::{mainnet, LruTokenStore, TokenId, TokenStore};
+    use std::num::NonZeroUsize;
+
+    let mut store = LruTokenStore::new(NonZeroUsize::new(1).unwrap());
+
+    store.insert(1, mainnet::DAI.clone());
+    store.insert(1, mainnet::USDC.clone());
+
+    assert!(!store.contains(1, TokenId::Symbol("DAI".to_string())));
+    assert!(store.symbol_addresses(1, "DAI").is_empty());
+    assert_eq!(
+        store
+            .get(1, TokenId::Symbol("USDC".to_string()))
+            .unwrap()
+            .address,
+        mainnet::USDC.address
+    );
+}
+
+#[cfg(feature = "lru-store")]
+#[test]
+fn test_ttl_store_eviction() {
+    use alloy::primitives::Address;
+    use alloy_erc20::{LruTokenStore, Token, TokenStore, TtlTokenStore};
+    use std::{num::NonZeroUsize, time::Duration};
+
+    let mut store = TtlTokenStore::new(
+        LruTokenStore::new(NonZeroUsize::new(1).unwrap()),
+        Duration::from_secs(60),
+    );
+
+    for byte in 0..=u8::MAX {
+        let address = Address::with_last_byte(byte);
+
+        store.insert(1, Token::new(1, address, byte.to_string(), 18));
+    }
+
+    // The fetch times of the evicted tokens are pruned
+    assert!(store.fetched_at(1, Address::with_last_byte(0)).is_none());
+    assert!(store
+        .fetched_at(1, Address::with_last_byte(u8::MAX))
+        .is_some());
+    assert_eq!(store.len(), 1);
+}
+
+#[test]
+fn test_remove_and_update() {
+    use alloy_erc20::{mainnet, BasicTokenStore, Entry, TokenId, TokenStore, TokenStoreMut};
+
+    let mut store = BasicTokenStore::new();
+
+    store.insert(1, mainnet::DAI.clone());
+    store.insert(1, mainnet::USDC.clone());
+    store.insert(1, mainnet::USDT.clone());
+    store.insert(1, mainnet::WETH.clone());
+
+    assert_eq!(store.len(), 4);
+
+    let dai = store.remove(1, TokenId::Symbol("DAI".to_string())).unwrap();
+
+    assert_eq!(dai.address, mainnet::DAI.address);
+    assert!(!store.contains(1, TokenId::Address(mainnet::DAI.address)));
+    assert!(!store.symbols(Some(1)).contains(&"DAI".to_string()));
+
+    // The token is reachable from its new symbol only
+    assert!(
+        store.update(1, TokenId::Address(mainnet::USDC.address), |token| {
+            token.symbol = "USDC.e".to_string();
+        })
+    );
+    assert!(!store.contains(1, TokenId::Symbol("USDC".to_string())));
+    assert!(store.contains(1, TokenId::Symbol("USDC.e".to_string())));
+
+    store.retain(|_, token| token.decimals == 18);
+
+    assert_eq!(store.len(), 1);
+    assert_eq!(store.symbols(None), ["WETH"]);
+
+    // Only the fields not identifying the token can be changed in place
+    match store.entry(1, TokenId::Symbol("WETH".to_string())) {
+        Entry::Occupied(mut entry) => {
+            entry.get_mut().set_decimals(6);
+
+            assert_eq!(entry.get().decimals, 6);
+        }
+        Entry::Vacant(_) => panic!("WETH should be present"),
+    }
+
+    assert_eq!(
+        store
+            .get(1, TokenId::Address(mainnet::WETH.address))
+            .unwrap()
+            .decimals,
+        6
+    );
+
+    match store.entry(1, TokenId::Symbol("WETH".to_string())) {
+        Entry::Occupied(entry) => assert_eq!(entry.remove().address, mainnet::WETH.address),
+        Entry::Vacant(_) => panic!("WETH should be present"),
+    }
+
+    assert!(store.is_empty());
+
+    store.insert(1, mainnet::DAI.clone());
+    store.clear();
+
+    assert!(store.is_empty());
+    assert!(store.symbols(None).is_empty());
+}
+
+#[tokio::test]
+async fn test_ttl_store() {
+    use alloy::{
+        primitives::{address, U256, U64},
+        providers::{mock::Asserter, ProviderBuilder},
+        sol_types::SolValue,
+    };
+    use alloy_erc20::{
+        BasicTokenStore, Clock, Entry, Erc20ProviderExt, TokenId, TokenMetadata, TokenStore,
+        TtlTokenStore,
+    };
+    use futures::{stream, StreamExt};
+    use std::{
+        sync::{Arc, Mutex, RwLock},
+        time::{Duration, Instant},
+    };
+
+    #[derive(Debug, Clone)]
+    struct ManualClock(Arc<Mutex<Instant>>);
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+    let dai = address!("6B175474E89094C44Da98b954EedeAC495271d0F");
+
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let mut store = TtlTokenStore::with_clock(
+        BasicTokenStore::new(),
+        Duration::from_secs(60),
+        ManualClock(now.clone()),
+    );
+
+    asserter.push_success(&U64::from(1));
+    asserter.push_success(&common::token_results("SAI", 18));
+
+    provider.get_token(dai, &mut store).await.unwrap();
+
+    // The metadata imported from a token list
+    store
+        .get_mut(1, TokenId::Address(dai))
+        .unwrap()
+        .set_metadata(Some(TokenMetadata {
+            logo_uri: Some("https://example.com/dai.png".to_string()),
+            tags: vec!["stablecoin".to_string()],
+            ..TokenMetadata::default()
+        }));
+
+    assert!(store.contains(1, TokenId::Address(dai)));
+    assert!(store.expired(1).is_empty());
+    assert_eq!(store.symbols(None), ["SAI"]);
+    assert_eq!(store.len(), 1);
+
+    *now.lock().unwrap() += Duration::from_secs(60);
+
+    assert_eq!(store.expired(1), [dai]);
+    assert!(store.is_empty());
+    assert!(store.symbols(Some(1)).is_empty());
+    assert!(store.symbols(None).is_empty());
+    assert!(store.addresses(None).is_empty());
+    assert!(matches!(
+        store.entry(1, TokenId::Address(dai)),
+        Entry::Vacant(_)
+    ));
+
+    let store = RwLock::new(store);
+
+    // A failed refresh is yielded, and retried on the next tick
+    asserter.push_failure_msg("rate limited");
+    asserter.push_failure_msg("rate limited");
+
+    let results = TtlTokenStore::refresh_on(&store, &provider, 1, stream::iter([()]))
+        .collect::<Vec<_>>()
+        .await;
+
+    assert!(results[0].is_err());
+    assert_eq!(store.read().unwrap().expired(1), [dai]);
+
+    asserter.push_success(&common::multicall_results(&[
+        Some(U256::from(1).abi_encode()),
+        Some(String::from("DAI").abi_encode()),
+        Some(U256::from(18).abi_encode()),
+        Some(String::from("Dai Stablecoin").abi_encode()),
+        None,
+        None,
+    ]));
+
+    let refreshed = TtlTokenStore::refresh(&store, &provider, 1).await.unwrap();
+
+    assert_eq!(refreshed, 1);
+
+    let store = store.into_inner().unwrap().into_inner();
+
+    let token = store.get(1, TokenId::Address(dai)).unwrap();
+
+    assert_eq!(token.symbol, "DAI");
+    assert!(!store.contains(1, TokenId::Symbol("SAI".to_string())));
+
+    // The metadata not retrieved from the contract is kept
+    let metadata = token.metadata.as_ref().unwrap();
+
+    assert_eq!(metadata.name.as_deref(), Some("Dai Stablecoin"));
+    assert_eq!(
+        metadata.logo_uri.as_deref(),
+        Some("https://example.com/dai.png")
+    );
+    assert_eq!(metadata.tags, ["stablecoin"]);
+}
+
+#[tokio::test]
+async fn test_ttl_store_zero_ttl() {
+    use alloy::{
+        primitives::{address, U64},
+        providers::{mock::Asserter, ProviderBuilder},
+    };
+    use alloy_erc20::{
+        BasicTokenStore, Entry, Erc20ProviderExt, LayeredTokenStore, Token, TokenId, TokenStore,
+        TtlTokenStore,
+    };
+    use std::time::Duration;
+
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+    let dai = address!("6B175474E89094C44Da98b954EedeAC495271d0F");
+    let mut store = TtlTokenStore::new(BasicTokenStore::new(), Duration::ZERO);
+
+    asserter.push_success(&U64::from(1));
+    asserter.push_success(&common::token_results("DAI", 18));
+
+    // The token is handed out even though it expires right away
+    let token = provider.get_token(dai, &mut store).await.unwrap();
+
+    assert_eq!(token.decimals, 18);
+    assert!(!store.contains(1, TokenId::Address(dai)));
+
+    let mut store = LayeredTokenStore::new(
+        TtlTokenStore::new(BasicTokenStore::new(), Duration::ZERO),
+        BasicTokenStore::new(),
+    );
+
+    match store.entry(1, TokenId::Address(dai)) {
+        Entry::Vacant(entry) => {
+            let token = entry.insert(Token::new(1, dai, "DAI".to_string(), 18));
+
+            assert_eq!(token.into_ref().decimals, 18);
+        }
+        Entry::Occupied(_) => panic!("DAI should be vacant"),
+    }
+
+    // The lower copy is still visible once the upper one expires
+    assert!(store.contains(1, TokenId::Address(dai)));
+}
+
+#[test]
+fn test_layered_store() {
+    use alloy_erc20::{
+        mainnet, BasicTokenStore, Entry, LayeredTokenStore, TokenId, TokenStore, TokenStoreMut,
+        WritePolicy,
+    };
+
+    let mut known = BasicTokenStore::new();
+    known.insert(1, mainnet::WETH.clone());
+
+    let mut cold = BasicTokenStore::new();
+    cold.insert(1, mainnet::DAI.clone());
+
+    let hot = BasicTokenStore::new();
+    let mut store = LayeredTokenStore::with_policy(
+        known,
+        LayeredTokenStore::new(hot, cold),
+        WritePolicy::Lower,
+    );
+
+    assert!(store.contains(1, TokenId::Symbol("WETH".to_string())));
+    assert!(store.contains(1, TokenId::Symbol("DAI".to_string())));
+
+    // Mutable accesses promote the cold token to the hot layer
+    match store.entry(1, TokenId::Symbol("DAI".to_string())) {
+        Entry::Occupied(entry) => assert_eq!(entry.into_mut().address, mainnet::DAI.address),
+        Entry::Vacant(_) => panic!("DAI should be present"),
+    }
+
+    let (known, layers) = store.into_inner();
+
+    assert!(layers
+        .upper()
+        .contains(1, TokenId::Address(mainnet::DAI.address)));
+    assert!(!known.contains(1, TokenId::Address(mainnet::DAI.address)));
+
+    let mut store = LayeredTokenStore::with_policy(known, layers, WritePolicy::Lower);
+
+    // Inserted tokens skip the read-only layer
+    store.insert(1, mainnet::USDC.clone());
+
+    assert_eq!(
+        store
+            .get(1, TokenId::Address(mainnet::USDC.address))
+            .unwrap()
+            .symbol,
+        "USDC"
+    );
+    assert!(!store
+        .upper()
+        .contains(1, TokenId::Address(mainnet::USDC.address)));
+    assert!(store
+        .lower()
+        .upper()
+        .contains(1, TokenId::Address(mainnet::USDC.address)));
+    assert!(store
+        .lower()
+        .lower()
+        .contains(1, TokenId::Address(mainnet::USDC.address)));
+
+    assert_eq!(
+        store
+            .remove(1, TokenId::Symbol("USDC".to_string()))
+            .unwrap()
+            .address,
+        mainnet::USDC.address
+    );
+    assert!(!store.contains(1, TokenId::Symbol("USDC".to_string())));
+}
+
+#[test]
+fn test_layered_store_read_only_layer() {
+    use alloy_erc20::{
+        mainnet, BasicTokenStore, LayeredTokenStore, TokenId, TokenStore, TokenStoreMut,
+        WritePolicy,
+    };
+
+    let mut known = BasicTokenStore::new();
+    known.insert(1, mainnet::WETH.clone());
+    known.insert(1, mainnet::DAI.clone());
+
+    let mut cache = BasicTokenStore::new();
+    cache.insert(1, mainnet::DAI.clone());
+
+    let mut store = LayeredTokenStore::with_policy(known, cache, WritePolicy::Lower);
+
+    // DAI is present in both layers
+    assert_eq!(store.len(), 2);
+
+    // The read-only token is copied to the lower layer to be mutated
+    store
+        .get_mut(1, TokenId::Symbol("WETH".to_string()))
+        .unwrap()
+        .set_decimals(8);
+
+    let weth = TokenId::Address(mainnet::WETH.address);
+
+    assert_eq!(store.get(1, weth.clone()).unwrap().decimals, 8);
+    assert_eq!(store.upper().get(1, weth.clone()).unwrap().decimals, 18);
+    assert_eq!(store.lower().get(1, weth.clone()).unwrap().decimals, 8);
+    assert_eq!(store.len(), 2);
+
+    // The removed tokens are masked in the read-only layer
+    assert!(store.remove(1, weth.clone()).is_some());
+    assert!(!store.contains(1, weth.clone()));
+    assert!(store.upper().contains(1, weth));
+    assert!(store.symbol_addresses(1, "WETH").is_empty());
+    assert_eq!(store.symbols(None), ["DAI"]);
+    assert_eq!(store.len(), 1);
+
+    store.clear();
+
+    assert!(!store.contains(1, TokenId::Symbol("DAI".to_string())));
+    assert!(store.addresses(None).is_empty());
+    assert!(store.is_empty());
+
+    // The tokens inserted again are visible
+    store.insert(1, mainnet::DAI.clone());
+
+    assert!(store.contains(1, TokenId::Symbol("DAI".to_string())));
+    assert_eq!(store.len(), 1);
+}
+
+#[test]
+fn test_layered_store_chains() {
+    use alloy_erc20::{mainnet, BasicTokenStore, LayeredTokenStore, Token, TokenStore};
+
+    let mut upper = BasicTokenStore::new();
+    upper.insert(1, mainnet::DAI.clone());
+
+    // The same address on another chain is another token
+    let mut lower = BasicTokenStore::new();
+    lower.insert(
+        10,
+        Token {
+            chain_id: 10,
+            ..mainnet::DAI.clone()
+        },
+    );
+
+    let store = LayeredTokenStore::new(upper, lower);
+
+    assert_eq!(store.len(), 2);
+    assert_eq!(store.chain_ids(), [1, 10]);
+    assert_eq!(
+        store.addresses(None),
+        [mainnet::DAI.address, mainnet::DAI.address]
+    );
+    assert_eq!(store.symbols(None), ["DAI", "DAI"]);
+    assert_eq!(store.addresses(Some(10)), [mainnet::DAI.address]);
+}
+
+/// Spawns an in-process server answering the few Redis commands used by
+/// [`alloy_erc20::RedisTokenStore`], and returns its URL.
+#[cfg(feature = "redis-store")]
+fn fake_redis() -> String {
+    use std::{
+        collections::{BTreeMap, HashMap},
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    fn read_command(reader: &mut impl BufRead) -> Option<Vec<String>> {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok().filter(|read| *read > 0)?;
+
+        let count = line.trim_end().strip_prefix('*')?.parse::<usize>().ok()?;
+        let mut args = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            line.clear();
+            reader.read_line(&mut line).ok()?;
+
+            let len = line.trim_end().strip_prefix('$')?.parse::<usize>().ok()?;
+            let mut arg = vec![0; len + 2];
+            reader.read_exact(&mut arg).ok()?;
+            arg.truncate(len);
+
+            args.push(String::from_utf8(arg).ok()?);
+        }
+
+        Some(args)
+    }
+
+    fn bulk_array<'a>(items: impl ExactSizeIterator<Item = &'a String>) -> String {
+        let mut reply = format!("*{}\r\n", items.len());
+
+        for item in items {
+            reply.push_str(&format!("${}\r\n{item}\r\n", item.len()));
+        }
+
+        reply
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("redis://{}", listener.local_addr().unwrap());
+    let hashes = Arc::new(Mutex::new(
+        HashMap::<String, BTreeMap<String, String>>::new(),
+    ));
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let hashes = hashes.clone();
+
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                while let Some(args) = read_command(&mut reader) {
+                    let mut hashes = hashes.lock().unwrap();
+
+                    let reply = match args[0].to_uppercase().as_str() {
+                        // A single iteration returns all the keys, and only
+                        // literal prefixes are matched, so the other glob
+                        // metacharacters must be escaped
+                        "SCAN" => {
+                            let mut chars = args[3].strip_suffix('*').unwrap().chars();
+                            let mut prefix = Some(String::new());
+
+                            while let Some(c) = chars.next() {
+                                match c {
+                                    '\\' => prefix.iter_mut().for_each(|p| p.extend(chars.next())),
+                                    '*' | '?' | '[' | ']' => prefix = None,
+                                    c => prefix.iter_mut().for_each(|p| p.push(c)),
+                                }
+                            }
+
+                            let keys = hashes
+                                .iter()
+                                .filter(|(key, hash)| {
+                                    prefix
+                                        .as_ref()
+                                        .is_some_and(|prefix| key.starts_with(prefix))
+                                        && !hash.is_empty()
+                                })
+                                .map(|(key, _)| key.clone())
+                                .collect::<Vec<_>>();
+
+                            format!("*2\r\n$1\r\n0\r\n{}", bulk_array(keys.iter()))
+                        }
+                        "HGET" => match hashes.get(&args[1]).and_then(|hash| hash.get(&args[2])) {
+                            Some(value) => format!("${}\r\n{value}\r\n", value.len()),
+                            None => "$-1\r\n".to_string(),
+                        },
+                        "HGETALL" => {
+                            let fields = hashes
+                                .get(&args[1])
+                                .into_iter()
+                                .flatten()
+                                .flat_map(|(field, value)| [field.clone(), value.clone()])
+                                .collect::<Vec<_>>();
+
+                            bulk_array(fields.iter())
+                        }
+                        "HSET" => {
+                            let hash = hashes.entry(args[1].clone()).or_default();
+                            let added = args[2..]
+                                .chunks(2)
+                                .filter(|pair| {
+                                    hash.insert(pair[0].clone(), pair[1].clone()).is_none()
+                                })
+                                .count();
+
+                            format!(":{added}\r\n")
+                        }
+                        "HDEL" => {
+                            let hash = hashes.entry(args[1].clone()).or_default();
+                            let removed = args[2..]
+                                .iter()
+                                .filter(|field| hash.remove(*field).is_some())
+                                .count();
+
+                            format!(":{removed}\r\n")
+                        }
+                        _ => "+OK\r\n".to_string(),
+                    };
+
+                    if stream.write_all(reply.as_bytes()).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    url
+}
+
+#[cfg(feature = "redis-store")]
+#[tokio::test]
+async fn test_redis_store() {
+    use alloy::primitives::Address;
+    use alloy_erc20::{
+        mainnet, RedisTokenStore, Token, TokenId, TokenMetadata, TokenStore, TokenStoreMut,
+    };
+
+    let url = fake_redis();
+
+    let mut worker = RedisTokenStore::open(url.as_str(), "tokens").await.unwrap();
+    let mut other = RedisTokenStore::open(url.as_str(), "tokens").await.unwrap();
+
+    worker.insert(
+        1,
+        mainnet::DAI.clone().with_metadata(TokenMetadata {
+            name: Some("Dai Stablecoin".to_string()),
+            ..TokenMetadata::default()
+        }),
+    );
+    worker.insert(1, mainnet::USDC.clone());
+    worker.flush().await.unwrap();
+
+    // A local miss is read through to the server
+    assert!(!other.contains(1, TokenId::Address(mainnet::DAI.address)));
+    assert_eq!(
+        other
+            .load(1, mainnet::DAI.address)
+            .await
+            .unwrap()
+            .unwrap()
+            .name(),
+        Some("Dai Stablecoin")
+    );
+    assert!(other
+        .load(1, mainnet::WETH.address)
+        .await
+        .unwrap()
+        .is_none());
+
+    other.pull().await.unwrap();
+
+    assert_eq!(other.len(), 2);
+    assert_eq!(
+        other
+            .get(1, TokenId::Symbol("USDC".to_string()))
+            .unwrap()
+            .address,
+        mainnet::USDC.address
+    );
+
+    // Canonical choices are shared too
+    let bridged = Token::new(1, Address::with_last_byte(1), "USDC".to_string(), 6);
+
+    worker.insert(1, bridged);
+    worker.set_canonical(1, "USDC".to_string(), mainnet::USDC.address);
+    worker.flush().await.unwrap();
+    other.pull().await.unwrap();
+
+    assert_eq!(
+        other
+            .get(1, TokenId::Symbol("USDC".to_string()))
+            .unwrap()
+            .address,
+        mainnet::USDC.address
+    );
+
+    // Canonical choices deleted on the server are dropped on pull, unless
+    // chosen again but not flushed
+    let mut conn = redis::Client::open(url.as_str())
+        .unwrap()
+        .get_multiplexed_async_connection()
+        .await
+        .unwrap();
+
+    redis::AsyncCommands::hdel::<_, _, ()>(&mut conn, "tokens:1:canonical", "USDC")
+        .await
+        .unwrap();
+
+    worker.set_canonical(1, "USDC".to_string(), mainnet::USDC.address);
+    worker.pull().await.unwrap();
+    other.pull().await.unwrap();
+
+    assert_eq!(other.canonical(1, "USDC"), None);
+    assert!(other.get(1, TokenId::Symbol("USDC".to_string())).is_none());
+    assert_eq!(worker.canonical(1, "USDC"), Some(mainnet::USDC.address));
+
+    // Removals are shared too, and unflushed tokens are kept on pull
+    other.remove(1, TokenId::Symbol("DAI".to_string()));
+    other.flush().await.unwrap();
+    worker.insert(1, mainnet::WETH.clone());
+    worker.pull().await.unwrap();
+
+    assert!(!worker.contains(1, TokenId::Symbol("DAI".to_string())));
+    assert!(worker.contains(1, TokenId::Symbol("WETH".to_string())));
+
+    // Not flushed, so not shared
+    drop(worker);
+
+    let store = RedisTokenStore::open(url.as_str(), "tokens").await.unwrap();
+
+    assert_eq!(store.len(), 2);
+    assert!(!store.contains(1, TokenId::Symbol("WETH".to_string())));
+}
+
+#[cfg(feature = "redis-store")]
+#[tokio::test]
+async fn test_redis_store_prefix() {
+    use alloy_erc20::{mainnet, RedisTokenStore, TokenStore};
+
+    let url = fake_redis();
+
+    // The prefix is matched literally, despite its glob metacharacters
+    let mut worker = RedisTokenStore::open(url.as_str(), "tokens[eu]*")
+        .await
+        .unwrap();
+
+    worker.insert(1, mainnet::DAI.clone());
+    worker.flush().await.unwrap();
+
+    let store = RedisTokenStore::open(url.as_str(), "tokens[eu]*")
+        .await
+        .unwrap();
+
+    assert!(store.contains(1, mainnet::DAI.address.into()));
+}
+
+#[cfg(feature = "redis-store")]
+#[tokio::test]
+async fn test_redis_store_get_or_retrieve() {
+    use alloy::{
+        primitives::U64,
+        providers::{mock::Asserter, ProviderBuilder},
+    };
+    use alloy_erc20::{mainnet, RedisTokenStore, TokenStore};
+
+    let url = fake_redis();
+
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let mut worker = RedisTokenStore::open(url.as_str(), "tokens").await.unwrap();
+    let mut other = RedisTokenStore::open(url.as_str(), "tokens").await.unwrap();
+
+    worker.insert(1, mainnet::USDC.clone());
+    worker.flush().await.unwrap();
+
+    // A token shared by another worker isn't retrieved from its contract
+    asserter.push_success(&U64::from(1));
+
+    let usdc = other
+        .get_or_retrieve(&provider, mainnet::USDC.address)
+        .await
+        .unwrap();
+
+    assert_eq!(usdc.symbol, "USDC");
+    assert!(asserter.read_q().is_empty());
+
+    // A token missing from the server is retrieved, and then shared
+    asserter.push_success(&U64::from(1));
+    asserter.push_success(&common::token_results("DAI", 18));
+
+    let dai = other
+        .get_or_retrieve(&provider, mainnet::DAI.address)
+        .await
+        .unwrap();
+
+    assert_eq!(dai.symbol, "DAI");
+    assert_eq!(dai.decimals, 18);
+
+    worker.pull().await.unwrap();
+
+    assert!(worker.contains(1, mainnet::DAI.address.into()));
+}
@@ -1,8 +1,17 @@
-use alloy::primitives::address;
-use alloy::providers::ProviderBuilder;
-use alloy_erc20::Erc20ProviderExt;
+use alloy::primitives::{address, b256, Address, Bytes, B256, U256, U64};
+use alloy::providers::{mock::Asserter, ProviderBuilder};
+use alloy::sol_types::SolValue;
+use alloy_erc20::{
+    is_native_token, register_native_token, BasicTokenStore, Erc20ProviderExt, StringEncoding,
+    Token, TokenMetadata, TokenStore, NATIVE_TOKEN_ADDRESS,
+};
+use bigdecimal::BigDecimal;
 use dotenv::dotenv;
-use std::env;
+use std::{env, str::FromStr};
+
+mod common;
+
+use common::multicall_results;
 
 #[tokio::test]
 async fn test_retrieve_token() {
@@ -17,3 +26,556 @@ async fn test_retrieve_token() {
 
     assert_eq!(dai.symbol, "DAI");
 }
+
+#[tokio::test]
+async fn test_retrieve_tokens() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    asserter.push_success(&multicall_results(&[
+        Some(U256::from(1).abi_encode()),
+        Some(String::from("DAI").abi_encode()),
+        Some(U256::from(18).abi_encode()),
+        Some(String::from("Dai Stablecoin").abi_encode()),
+        Some(
+            b256!("dbb8cf42e1ecb028be3f3dbc922e1d878b963f411dc388ced501601c60f7c6f7").abi_encode(),
+        ),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    ]));
+
+    let tokens = provider
+        .retrieve_tokens(&[
+            address!("6B175474E89094C44Da98b954EedeAC495271d0F"),
+            address!("0000000000000000000000000000000000000001"),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0].as_ref().unwrap().symbol, "DAI");
+    assert_eq!(tokens[0].as_ref().unwrap().decimals, 18);
+    assert_eq!(tokens[0].as_ref().unwrap().name(), Some("Dai Stablecoin"));
+    assert_eq!(tokens[0].as_ref().unwrap().chain_id, 1);
+    assert!(
+        tokens[0]
+            .as_ref()
+            .unwrap()
+            .metadata
+            .as_ref()
+            .unwrap()
+            .capabilities
+            .permit
+    );
+    assert!(tokens[1].is_err());
+}
+
+#[tokio::test]
+async fn test_retrieve_tokens_failure() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let dai = address!("6B175474E89094C44Da98b954EedeAC495271d0F");
+
+    // The failure of the batch is reported for each of its tokens
+    asserter.push_failure_msg("rate limited");
+    asserter.push_success(&U64::from(1));
+
+    let tokens = provider
+        .retrieve_tokens(&[dai, NATIVE_TOKEN_ADDRESS])
+        .await
+        .unwrap();
+
+    let err = tokens[0].as_ref().unwrap_err();
+
+    assert_eq!(err.token, dai.into());
+    assert!(err.to_string().contains("rate limited"));
+    assert_eq!(tokens[1].as_ref().unwrap().symbol, "ETH");
+
+    // Unless the chain can't be known
+    asserter.push_failure_msg("rate limited");
+    asserter.push_failure_msg("rate limited");
+
+    let err = provider.retrieve_tokens(&[dai]).await.unwrap_err();
+
+    assert_eq!(err.token, dai.into());
+}
+
+#[tokio::test]
+async fn test_retrieve_tokens_batches() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    // The chain id and 5 calls for each of 150 tokens are split in two
+    // batches, the calls of a token being kept in the same batch
+    let addresses = (0..150u64)
+        .map(|i| Address::from_word(U256::from(i + 1).into()))
+        .collect::<Vec<_>>();
+
+    let mut results = vec![Some(U256::from(1).abi_encode())];
+
+    for i in 0..99 {
+        results.extend(common::token_calls(&format!("T{i}"), 18));
+    }
+
+    // The latest block is resolved first, for both batches to be made at it
+    asserter.push_success(&U64::from(100));
+    asserter.push_success(&multicall_results(&results));
+    asserter.push_failure_msg("rate limited");
+
+    let tokens = provider.retrieve_tokens(&addresses).await.unwrap();
+
+    assert_eq!(tokens.len(), 150);
+    assert_eq!(tokens[98].as_ref().unwrap().symbol, "T98");
+    assert_eq!(tokens[98].as_ref().unwrap().decimals, 18);
+    assert_eq!(tokens[98].as_ref().unwrap().name(), None);
+    assert!(tokens[99..].iter().all(Result::is_err));
+    assert!(asserter.read_q().is_empty());
+}
+
+#[tokio::test]
+async fn test_retrieve_token_metadata() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let dai = address!("6B175474E89094C44Da98b954EedeAC495271d0F");
+
+    asserter.push_success(&U64::from(1));
+
+    // The token and its metadata are retrieved at once
+    asserter.push_success(&multicall_results(&[
+        Some(String::from("DAI").abi_encode()),
+        Some(U256::from(18).abi_encode()),
+        Some(String::from("Dai Stablecoin").abi_encode()),
+        Some(
+            b256!("dbb8cf42e1ecb028be3f3dbc922e1d878b963f411dc388ced501601c60f7c6f7").abi_encode(),
+        ),
+        None,
+    ]));
+
+    let token = provider.retrieve_token(dai).await.unwrap();
+    assert_eq!(token.chain_id, 1);
+    assert_eq!(token.symbol, "DAI");
+    assert_eq!(token.decimals, 18);
+    assert!(asserter.read_q().is_empty());
+
+    let metadata = token.metadata.unwrap();
+
+    assert_eq!(metadata.name.as_deref(), Some("Dai Stablecoin"));
+    assert!(metadata.capabilities.permit);
+    assert!(!metadata.capabilities.erc4626);
+}
+
+#[tokio::test]
+async fn test_retrieve_token_without_multicall() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let dai = address!("6B175474E89094C44Da98b954EedeAC495271d0F");
+
+    asserter.push_success(&U64::from(1));
+    // Multicall3 has no code on the chain
+    asserter.push_success(&Bytes::new());
+    asserter.push_success(&Bytes::from(String::from("DAI").abi_encode()));
+    asserter.push_success(&B256::with_last_byte(18));
+
+    let token = provider.retrieve_token(dai).await.unwrap();
+
+    assert_eq!(token.symbol, "DAI");
+    assert_eq!(token.decimals, 18);
+    assert!(token.metadata.is_none());
+
+    // Or the aggregated call reverts
+    asserter.push_success(&U64::from(1));
+    asserter.push_failure_msg("execution reverted");
+    asserter.push_success(&Bytes::from(String::from("DAI").abi_encode()));
+    asserter.push_success(&B256::with_last_byte(18));
+
+    assert_eq!(provider.retrieve_token(dai).await.unwrap().symbol, "DAI");
+}
+
+#[tokio::test]
+async fn test_retrieve_bytes32_symbol() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    // MKR returns its symbol as a bytes32
+    asserter.push_success(&b256!(
+        "4d4b520000000000000000000000000000000000000000000000000000000000"
+    ));
+
+    let (symbol, encoding) = provider
+        .retrieve_symbol(address!("9f8F72aA9304c8B593d555F12eF6589cC3A579A2"))
+        .await
+        .unwrap();
+
+    assert_eq!(symbol, "MKR");
+    assert_eq!(encoding, StringEncoding::Bytes32);
+}
+
+#[tokio::test]
+async fn test_balances() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let dai = address!("6B175474E89094C44Da98b954EedeAC495271d0F");
+    let usdc = address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+
+    let mut store = BasicTokenStore::new();
+    store.insert(1, Token::new(1, dai, "DAI".to_string(), 18));
+
+    // USDC and ETH are missing from the store, but ETH is the native asset
+    asserter.push_success(&U64::from(1));
+
+    asserter.push_success(&multicall_results(&[
+        Some(U256::from(1).abi_encode()),
+        Some(String::from("USDC").abi_encode()),
+        Some(U256::from(6).abi_encode()),
+        Some(String::from("USD Coin").abi_encode()),
+        None,
+        None,
+    ]));
+
+    asserter.push_success(&multicall_results(&[
+        Some(U256::from(10).pow(U256::from(18)).abi_encode()),
+        Some(U256::from(2_500_000).abi_encode()),
+        Some(U256::from(10).pow(U256::from(17)).abi_encode()),
+        Some(U256::ZERO.abi_encode()),
+        None,
+        Some(U256::ZERO.abi_encode()),
+    ]));
+
+    let holders = [
+        address!("0000000000000000000000000000000000000002"),
+        address!("0000000000000000000000000000000000000003"),
+    ];
+
+    let balances = provider
+        .balances(&holders, &[dai, usdc, NATIVE_TOKEN_ADDRESS], &mut store)
+        .await
+        .unwrap();
+
+    assert_eq!(balances.len(), 2);
+    assert_eq!(balances[0].len(), 3);
+    assert_eq!(*balances[0][0].as_ref().unwrap(), BigDecimal::from(1));
+    assert_eq!(
+        *balances[0][1].as_ref().unwrap(),
+        BigDecimal::from_str("2.5").unwrap()
+    );
+    assert_eq!(
+        *balances[0][2].as_ref().unwrap(),
+        BigDecimal::from_str("0.1").unwrap()
+    );
+    assert_eq!(*balances[1][0].as_ref().unwrap(), BigDecimal::from(0));
+    assert!(balances[1][1].is_err());
+    assert_eq!(*balances[1][2].as_ref().unwrap(), BigDecimal::from(0));
+    assert!(store.contains(1, usdc.into()));
+    assert!(store.contains(1, NATIVE_TOKEN_ADDRESS.into()));
+}
+
+#[tokio::test]
+async fn test_balances_unknown_token() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let dai = address!("6B175474E89094C44Da98b954EedeAC495271d0F");
+    let unknown = address!("0000000000000000000000000000000000000001");
+
+    let mut store = BasicTokenStore::new();
+    store.insert(1, Token::new(1, dai, "DAI".to_string(), 18));
+
+    asserter.push_success(&U64::from(1));
+
+    // The unknown token isn't an ERC-20 contract
+    asserter.push_success(&multicall_results(&[
+        Some(U256::from(1).abi_encode()),
+        None,
+        None,
+        None,
+        None,
+        None,
+    ]));
+
+    asserter.push_success(&multicall_results(&[
+        Some(U256::from(10).pow(U256::from(18)).abi_encode()),
+        Some(U256::ZERO.abi_encode()),
+    ]));
+
+    let holders = [
+        address!("0000000000000000000000000000000000000002"),
+        address!("0000000000000000000000000000000000000003"),
+    ];
+
+    let balances = provider
+        .balances(&holders, &[dai, unknown], &mut store)
+        .await
+        .unwrap();
+
+    assert_eq!(*balances[0][0].as_ref().unwrap(), BigDecimal::from(1));
+    assert_eq!(*balances[1][0].as_ref().unwrap(), BigDecimal::from(0));
+
+    for row in &balances {
+        let err = row[1].as_ref().unwrap_err();
+
+        assert_eq!(err.token, unknown.into());
+        assert!(err.to_string().contains("not present in the store"));
+    }
+
+    assert!(!store.contains(1, unknown.into()));
+}
+
+#[tokio::test]
+async fn test_balances_batches() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let dai = address!("6B175474E89094C44Da98b954EedeAC495271d0F");
+
+    let mut store = BasicTokenStore::new();
+    store.insert(1, Token::new(1, dai, "DAI".to_string(), 18));
+
+    let holders = (0..501u64)
+        .map(|i| Address::from_word(U256::from(i + 1).into()))
+        .collect::<Vec<_>>();
+
+    let balance = |i: u64| Some((U256::from(i) * U256::from(10).pow(U256::from(18))).abi_encode());
+
+    asserter.push_success(&U64::from(1));
+    // The latest block is resolved first, for both batches to be made at it
+    asserter.push_success(&U64::from(100));
+    asserter.push_success(&multicall_results(
+        &(0..500).map(balance).collect::<Vec<_>>(),
+    ));
+    asserter.push_success(&multicall_results(&[balance(500)]));
+
+    let balances = provider
+        .balances(&holders, &[dai], &mut store)
+        .await
+        .unwrap();
+
+    assert_eq!(balances.len(), 501);
+    assert_eq!(*balances[499][0].as_ref().unwrap(), BigDecimal::from(499));
+    assert_eq!(*balances[500][0].as_ref().unwrap(), BigDecimal::from(500));
+}
+
+#[tokio::test]
+async fn test_retrieve_unknown_native_token() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    asserter.push_success(&U64::from(10));
+
+    let err = provider
+        .retrieve_token(NATIVE_TOKEN_ADDRESS)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("chain 10 is unknown"));
+}
+
+#[tokio::test]
+async fn test_registered_native_token() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    // POL is the native asset of Polygon, at the address of its contract
+    let pol = address!("0000000000000000000000000000000000001010");
+
+    register_native_token(Token::new(137, pol, "POL".to_string(), 18));
+
+    assert!(is_native_token(137, pol));
+    assert!(!is_native_token(137, NATIVE_TOKEN_ADDRESS));
+    assert!(!is_native_token(1, pol));
+    assert!(is_native_token(10, NATIVE_TOKEN_ADDRESS));
+
+    asserter.push_success(&U64::from(137));
+    asserter.push_success(&U256::from(1_500_000_000_000_000_000u64));
+
+    let balance = provider
+        .balance_of(pol, address!("0000000000000000000000000000000000000002"))
+        .await
+        .unwrap();
+
+    assert_eq!(balance, BigDecimal::from_str("1.5").unwrap());
+
+    asserter.push_success(&U64::from(137));
+
+    assert_eq!(provider.retrieve_token(pol).await.unwrap().symbol, "POL");
+    assert!(asserter.read_q().is_empty());
+}
+
+#[tokio::test]
+async fn test_native_balance_of() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    asserter.push_success(&U64::from(1));
+    asserter.push_success(&U256::from(1_500_000_000_000_000_000u64));
+
+    let balance = provider
+        .balance_of(
+            NATIVE_TOKEN_ADDRESS,
+            address!("0000000000000000000000000000000000000002"),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(balance, BigDecimal::from_str("1.5").unwrap());
+}
+
+#[tokio::test]
+async fn test_balance_of() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    // Only the decimals of the token are retrieved
+    asserter.push_success(&B256::from(U256::from(1_500_000)));
+    asserter.push_success(&B256::with_last_byte(6));
+
+    let balance = provider
+        .balance_of(
+            address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+            address!("0000000000000000000000000000000000000002"),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(balance, BigDecimal::from_str("1.5").unwrap());
+    assert!(asserter.read_q().is_empty());
+}
+
+#[tokio::test]
+async fn test_balance_diff() {
+    use alloy::eips::BlockId;
+
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let dai = Token::new(
+        1,
+        address!("6B175474E89094C44Da98b954EedeAC495271d0F"),
+        "DAI".to_string(),
+        18,
+    );
+
+    // 2 DAI at the first block, then 0.5 DAI
+    asserter.push_success(&B256::from(U256::from(2_000_000_000_000_000_000_u128)));
+    asserter.push_success(&B256::from(U256::from(500_000_000_000_000_000_u128)));
+
+    let delta = provider
+        .balance_diff(
+            &dai,
+            address!("0000000000000000000000000000000000000001"),
+            BlockId::number(1),
+            BlockId::number(2),
+        )
+        .await
+        .unwrap();
+
+    assert!(delta.is_negative());
+    assert_eq!(delta.to_decimal(), BigDecimal::from_str("-1.5").unwrap());
+}
+
+#[tokio::test]
+async fn test_safe_transfer_no_code() {
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    // Calling an address without code returns nothing, like USDT does
+    asserter.push_success(&Bytes::new());
+    asserter.push_success(&Bytes::new());
+
+    let err = provider
+        .safe_transfer(
+            address!("0000000000000000000000000000000000000001"),
+            address!("0000000000000000000000000000000000000002"),
+            U256::from(1),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("without code"));
+    assert!(asserter.read_q().is_empty());
+}
+
+#[tokio::test]
+async fn test_safe_transfer_reverted() {
+    use alloy::{
+        rpc::json_rpc::ErrorPayload,
+        sol_types::{Revert, SolError},
+    };
+
+    let asserter = Asserter::new();
+    let provider = ProviderBuilder::new().on_mocked_client(asserter.clone());
+
+    let reason = Revert::from("ERC20: transfer amount exceeds balance").abi_encode();
+
+    asserter.push_failure(ErrorPayload {
+        code: 3,
+        message: "execution reverted".into(),
+        data: Some(serde_json::value::to_raw_value(&Bytes::from(reason)).unwrap()),
+    });
+
+    let err = provider
+        .safe_transfer(
+            address!("6B175474E89094C44Da98b954EedeAC495271d0F"),
+            address!("0000000000000000000000000000000000000002"),
+            U256::from(1),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("reverted"));
+    assert!(err.to_string().contains("exceeds balance"));
+}
+
+#[test]
+fn test_token_identity() {
+    use alloy_erc20::{arbitrum, mainnet};
+    use std::collections::{BTreeSet, HashSet};
+
+    let scam = Token::new(
+        1,
+        address!("0000000000000000000000000000000000000bad"),
+        "USDC".to_string(),
+        6,
+    );
+
+    assert_ne!(*mainnet::USDC, *arbitrum::USDC);
+    assert_ne!(*mainnet::ETH, *arbitrum::ETH);
+    assert_ne!(*mainnet::USDC, scam);
+
+    // The symbol and decimals don't matter
+    let renamed = Token {
+        symbol: "USDC.e".to_string(),
+        ..mainnet::USDC.clone()
+    };
+
+    assert_eq!(*mainnet::USDC, renamed);
+
+    // Nor does the metadata
+    let described =
+        Token::new(1, mainnet::USDC.address, "USDC".to_string(), 6).with_metadata(TokenMetadata {
+            name: Some("USD Coin".to_string()),
+            ..Default::default()
+        });
+
+    assert_eq!(*mainnet::USDC, described);
+
+    let tokens = [mainnet::USDC.clone(), renamed, arbitrum::USDC.clone(), scam];
+
+    assert_eq!(tokens.iter().collect::<HashSet<_>>().len(), 3);
+    assert_eq!(
+        tokens
+            .iter()
+            .collect::<BTreeSet<_>>()
+            .first()
+            .unwrap()
+            .address,
+        tokens[3].address
+    );
+}
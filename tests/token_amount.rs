@@ -0,0 +1,178 @@
+use std::str::FromStr;
+
+use alloy::primitives::U256;
+use alloy_erc20::{arbitrum, mainnet, TokenAmount};
+use bigdecimal::BigDecimal;
+
+#[test]
+fn test_token_amount_arithmetic() {
+    let a = TokenAmount::new(&mainnet::USDC, U256::from(1_500_000));
+    let b = TokenAmount::new(&mainnet::USDC, U256::from(500_000));
+
+    assert_eq!(a.checked_add(&b).unwrap().raw(), U256::from(2_000_000));
+    assert_eq!(a.checked_sub(&b).unwrap().raw(), U256::from(1_000_000));
+    assert!(b.checked_sub(&a).is_err());
+
+    // Amounts of different tokens can't be mixed, even sharing a symbol
+    let bridged = TokenAmount::new(&arbitrum::USDC, U256::from(500_000));
+
+    assert!(a.checked_add(&bridged).is_err());
+
+    assert_eq!(
+        a.checked_mul_ratio(U256::from(2), U256::from(3))
+            .unwrap()
+            .raw(),
+        U256::from(1_000_000)
+    );
+    assert!(a.checked_mul_ratio(U256::from(1), U256::ZERO).is_err());
+
+    // The intermediate product doesn't overflow
+    let max = TokenAmount::new(&mainnet::USDC, U256::MAX);
+
+    assert_eq!(
+        max.checked_mul_ratio(U256::MAX, U256::MAX).unwrap().raw(),
+        U256::MAX
+    );
+    assert!(max.checked_mul_ratio(U256::from(2), U256::from(1)).is_err());
+    assert!(max.checked_add(&b).is_err());
+}
+
+#[test]
+fn test_token_amount_conversions() {
+    let amount = TokenAmount::parse(&mainnet::USDC, "1,234.56 USDC").unwrap();
+
+    assert_eq!(amount.raw(), U256::from(1_234_560_000_u64));
+    assert_eq!(
+        amount.to_decimal(),
+        BigDecimal::from_str("1234.56").unwrap()
+    );
+    assert_eq!(amount.to_f64(), 1234.56);
+    assert_eq!(
+        TokenAmount::from_f64(&mainnet::USDC, 1234.56).unwrap(),
+        amount
+    );
+    assert_eq!(
+        TokenAmount::from_decimal(&mainnet::USDC, &BigDecimal::from_str("1234.56").unwrap())
+            .unwrap(),
+        amount
+    );
+
+    assert!(TokenAmount::parse(&mainnet::USDC, "1.0000001").is_err());
+    assert!(TokenAmount::parse(&mainnet::USDC, "-1").is_err());
+    assert!(TokenAmount::parse(&mainnet::USDC, "abc").is_err());
+    assert!(TokenAmount::from_f64(&mainnet::USDC, f64::NAN).is_err());
+}
+
+#[test]
+fn test_token_amount_display() {
+    let amount = TokenAmount::new(&mainnet::USDC, U256::from(1_234_567_890_u64));
+
+    assert_eq!(amount.to_string(), "1,234.56789 USDC");
+    assert_eq!(format!("{amount:.2}"), "1,234.57 USDC");
+    assert_eq!(format!("{amount:#.0}"), "1,235");
+    assert_eq!(format!("{amount:>16.2}"), "   1,234.57 USDC");
+    assert_eq!(format!("{amount:*<#12}"), "1,234.56789*");
+    assert_eq!(format!("{amount:^#10.0}"), "  1,235   ");
+    assert_eq!(format!("{amount:4}"), "1,234.56789 USDC");
+
+    let amount = TokenAmount::new(&mainnet::WETH, U256::from(10).pow(U256::from(24)));
+
+    assert_eq!(amount.to_string(), "1,000,000 WETH");
+    assert_eq!(TokenAmount::zero(&mainnet::WETH).to_string(), "0 WETH");
+    assert_eq!(
+        TokenAmount::parse(&mainnet::WETH, &amount.to_string()).unwrap(),
+        amount
+    );
+}
+
+#[test]
+fn test_parse_amount() {
+    use alloy_erc20::Rounding;
+
+    let usdc = &*mainnet::USDC;
+
+    assert_eq!(
+        usdc.parse_amount("12.5", Rounding::Exact).unwrap(),
+        U256::from(12_500_000)
+    );
+    assert_eq!(
+        usdc.parse_amount("1.5e3", Rounding::Exact).unwrap(),
+        U256::from(1_500_000_000_u64)
+    );
+    assert_eq!(
+        usdc.parse_amount("25E-7", Rounding::HalfEven).unwrap(),
+        U256::from(2)
+    );
+    assert_eq!(
+        usdc.parse_amount("MAX", Rounding::Exact).unwrap(),
+        U256::MAX
+    );
+
+    // Excess precision is either rejected or rounded
+    assert!(usdc.parse_amount("0.0000015", Rounding::Exact).is_err());
+    assert_eq!(
+        usdc.parse_amount("0.0000015", Rounding::Down).unwrap(),
+        U256::from(1)
+    );
+    assert_eq!(
+        usdc.parse_amount("0.0000011", Rounding::Up).unwrap(),
+        U256::from(2)
+    );
+    assert_eq!(
+        usdc.parse_amount("0.0000015", Rounding::HalfUp).unwrap(),
+        U256::from(2)
+    );
+
+    assert!(usdc.parse_amount("1e100", Rounding::Exact).is_err());
+    // Rejected before being scaled
+    assert!(usdc.parse_amount("1e10000000", Rounding::Exact).is_err());
+    assert!(usdc.parse_amount("1e-10000000", Rounding::Exact).is_err());
+    assert!(usdc.parse_amount("-1", Rounding::Down).is_err());
+    assert!(usdc.parse_amount("1.2.3", Rounding::Exact).is_err());
+
+    let amount = BigDecimal::from_str("12.5").unwrap();
+
+    assert_eq!(
+        usdc.get_balance(usdc.from_decimal(&amount, Rounding::Exact).unwrap()),
+        amount
+    );
+}
+
+#[test]
+fn test_token_delta() {
+    use alloy::primitives::I256;
+    use alloy_erc20::TokenDelta;
+
+    let before = TokenAmount::new(&mainnet::USDC, U256::from(2_500_000));
+    let after = TokenAmount::new(&mainnet::USDC, U256::from(1_000_000));
+
+    let loss = after.checked_delta(&before).unwrap();
+
+    assert!(loss.is_negative());
+    assert_eq!(loss.raw(), I256::try_from(-1_500_000).unwrap());
+    assert_eq!(loss.to_decimal(), BigDecimal::from_str("-1.5").unwrap());
+    assert_eq!(loss.unsigned_abs().raw(), U256::from(1_500_000));
+    assert_eq!(loss.to_string(), "-1.5 USDC");
+
+    let gain = before.checked_delta(&after).unwrap();
+
+    assert_eq!(format!("{gain:+.2}"), "+1.50 USDC");
+    assert_eq!(format!("{loss:12}"), "   -1.5 USDC");
+    assert!(loss.checked_add(&gain).unwrap().is_zero());
+
+    // The delta of amounts of different tokens, or too far apart, fails
+    let bridged = TokenAmount::new(&arbitrum::USDC, U256::from(1_000_000));
+
+    assert!(bridged.checked_delta(&before).is_err());
+    assert!(TokenAmount::new(&mainnet::USDC, U256::MAX)
+        .checked_delta(&TokenAmount::zero(&mainnet::USDC))
+        .is_err());
+    assert!(TokenDelta::new(&mainnet::USDC, I256::MAX)
+        .checked_add(&gain)
+        .is_err());
+
+    assert_eq!(
+        mainnet::USDC.get_signed_balance(I256::MIN + I256::ONE),
+        -mainnet::USDC.get_balance(U256::from(I256::MAX.unsigned_abs()))
+    );
+}
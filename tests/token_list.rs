@@ -0,0 +1,163 @@
+#![cfg(feature = "token-list")]
+
+use alloy::primitives::address;
+use alloy_erc20::{BasicTokenStore, Token, TokenId, TokenList, TokenListError, TokenStore};
+
+const LIST: &str = r#"{
+    "name": "Test List",
+    "timestamp": "2024-01-01T00:00:00.000Z",
+    "version": { "major": 1, "minor": 2, "patch": 0 },
+    "tags": {
+        "stablecoin": { "name": "Stablecoin", "description": "Pegged to a fiat currency" }
+    },
+    "tokens": [
+        {
+            "chainId": 1,
+            "address": "0x6b175474e89094c44da98b954eedeac495271d0f",
+            "name": "Dai Stablecoin",
+            "symbol": "DAI",
+            "decimals": 18,
+            "logoURI": "https://example.com/dai.png",
+            "tags": ["stablecoin"]
+        },
+        {
+            "chainId": 42161,
+            "address": "0xaf88d065e77c8cC2239327C5EDb3A432268e5831",
+            "name": "USD Coin",
+            "symbol": "USDC",
+            "decimals": 6
+        }
+    ]
+}"#;
+
+#[test]
+fn test_import_token_list() {
+    let list = TokenList::from_json(LIST).unwrap();
+
+    assert_eq!(list.version.minor, 2);
+    assert_eq!(
+        list.tokens[0].logo_uri.as_deref(),
+        Some("https://example.com/dai.png")
+    );
+
+    let mut store = BasicTokenStore::new();
+    list.insert_into(&mut store);
+
+    let dai = store.get(1, TokenId::Symbol("DAI".to_string())).unwrap();
+    assert_eq!(
+        dai.address,
+        address!("6B175474E89094C44Da98b954EedeAC495271d0F")
+    );
+    assert_eq!(dai.decimals, 18);
+    assert_eq!(dai.name(), Some("Dai Stablecoin"));
+
+    assert_eq!(dai.chain_id, 1);
+
+    let metadata = dai.metadata.as_ref().unwrap();
+    assert_eq!(metadata.tags, ["stablecoin"]);
+
+    assert!(store.contains(
+        42161,
+        TokenId::Address(address!("af88d065e77c8cC2239327C5EDb3A432268e5831"))
+    ));
+}
+
+#[test]
+fn test_export_token_list() {
+    let mut store = BasicTokenStore::new();
+    TokenList::from_json(LIST).unwrap().insert_into(&mut store);
+
+    let list = TokenList::from_store("Exported", &[1, 42161], &store).unwrap();
+
+    assert_eq!(list.tokens.len(), 2);
+    assert!(list
+        .tokens
+        .iter()
+        .any(|token| token.name == "Dai Stablecoin"));
+
+    let json = list.to_json().unwrap();
+    assert!(json.contains("0x6B175474E89094C44Da98b954EedeAC495271d0F"));
+    assert_eq!(TokenList::from_json(&json).unwrap(), list);
+}
+
+#[test]
+fn test_invalid_token_list() {
+    let duplicate = LIST
+        .replace(
+            "0xaf88d065e77c8cC2239327C5EDb3A432268e5831",
+            "0x6b175474e89094c44da98b954eedeac495271d0f",
+        )
+        .replace("42161", "1");
+
+    assert!(matches!(
+        TokenList::from_json(&duplicate),
+        Err(TokenListError::Duplicate(_, 1))
+    ));
+
+    let undefined_tag = LIST.replace(r#"["stablecoin"]"#, r#"["unknown"]"#);
+
+    assert!(matches!(
+        TokenList::from_json(&undefined_tag),
+        Err(TokenListError::InvalidToken(_, 1, _))
+    ));
+
+    let invalid_symbol = LIST.replace(r#""USDC""#, r#""USD C""#);
+
+    assert!(matches!(
+        TokenList::from_json(&invalid_symbol),
+        Err(TokenListError::InvalidToken(_, 42161, _))
+    ));
+
+    let invalid_timestamp = LIST.replace("2024-01-01T00:00:00.000Z", "yesterday");
+
+    assert!(matches!(
+        TokenList::from_json(&invalid_timestamp),
+        Err(TokenListError::InvalidList(_))
+    ));
+
+    let invalid_tag = LIST.replace(
+        "Pegged to a fiat currency",
+        "Pegged to a fiat currency (USD, EUR)",
+    );
+
+    assert!(matches!(
+        TokenList::from_json(&invalid_tag),
+        Err(TokenListError::InvalidList(_))
+    ));
+
+    let invalid_logo = LIST.replace("https://example.com/dai.png", "dai.png");
+
+    assert!(matches!(
+        TokenList::from_json(&invalid_logo),
+        Err(TokenListError::InvalidToken(_, 1, _))
+    ));
+
+    let nested_extensions = LIST.replace(
+        r#""decimals": 6"#,
+        r#""decimals": 6, "extensions": { "a": { "b": { "c": { "d": 1 } } } }"#,
+    );
+
+    assert!(matches!(
+        TokenList::from_json(&nested_extensions),
+        Err(TokenListError::InvalidToken(_, 42161, _))
+    ));
+}
+
+#[test]
+fn test_export_invalid_token_list() {
+    let mut store = BasicTokenStore::new();
+    store.insert(
+        1,
+        Token::new(
+            1,
+            address!("6B175474E89094C44Da98b954EedeAC495271d0F"),
+            "DAI STABLE".to_string(),
+            18,
+        ),
+    );
+
+    assert!(matches!(
+        TokenList::from_store("Exported", &[1], &store),
+        Err(TokenListError::InvalidToken(_, 1, _))
+    ));
+}